@@ -1,6 +1,13 @@
-use std::path::Path;
+use std::{fs::read_to_string, path::Path};
 
-use clap::{command, value_parser, Arg, ArgAction, Command};
+use clap::{command, parser::ValueSource, value_parser, Arg, ArgAction, ArgMatches, Command};
+use ipnet::IpNet;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Url,
+};
+
+use crate::output::{Column, OutputFormat, Template, JOIN_SEPARATOR};
 
 #[derive(Debug, Default)]
 pub struct AppArgs {
@@ -13,9 +20,30 @@ pub struct AppArgs {
     // 爆破模式，与字典模式只能启用一个
     pub length: (usize, usize),
 
-    // 输出文件，默认使用 "{target}.txt"
+    // 输出文件，默认使用 "{target}.out.{扩展名}"
     pub output_path: String,
 
+    // 输出格式
+    pub format: OutputFormat,
+
+    // 自定义输出模板，指定时输出格式为 template
+    pub template: Option<Template>,
+
+    // CSV / TSV 输出的列
+    pub columns: Vec<Column>,
+
+    // 上一次扫描的结果（JSONL 文件或者 SQLite 数据库），指定时输出变化报告
+    pub diff_path: Option<String>,
+
+    // 变化报告的输出路径，默认使用 "{target}.diff.txt"
+    pub diff_output_path: String,
+
+    // silent 模式，stdout 只输出结果，运行信息只输出警告和错误到 stderr
+    pub silent: bool,
+
+    // silent 模式下 stdout 的输出格式，指定了逐行输出的格式时使用该格式，否则只输出域名
+    pub stdout_format: Option<OutputFormat>,
+
     // 任务数量
     pub task_count: usize,
 
@@ -27,6 +55,61 @@ pub struct AppArgs {
 
     // 是否跳过爆破出域名的 title 获取
     pub fetch_title: bool,
+
+    // EDNS Client Subnet 列表，为空时不发送 ECS 查询
+    pub ecs_subnets: Vec<IpNet>,
+
+    // CDN / 云厂商识别数据，留空使用内置数据
+    pub provider_data_path: Option<String>,
+
+    // 是否跳过 CDN 后面域名的 HTTP 探测
+    pub skip_cdn: bool,
+
+    // MaxMind 格式的 ASN 数据库
+    pub asn_db_path: Option<String>,
+
+    // MaxMind 格式的 City / Country 数据库
+    pub geo_db_path: Option<String>,
+
+    // 扫描范围规则文件
+    pub scope_path: Option<String>,
+
+    // HTTP 探测的端口列表
+    pub ports: Vec<u16>,
+
+    // 技术栈指纹规则，留空使用内置规则
+    pub tech_rules_path: Option<String>,
+
+    // HTTP 请求使用的代理，认证信息已经放在 URL 里
+    pub proxy: Option<Url>,
+
+    // 解析到内网地址的域名不走代理
+    pub proxy_bypass_internal: bool,
+
+    // 自定义请求头，(名称, 值)
+    pub headers: Vec<(String, String)>,
+
+    // 请求时携带的 Cookie
+    pub cookie: Option<String>,
+
+    // User-Agent 列表，每次请求随机选择一个，为空时不设置
+    pub user_agents: Vec<String>,
+
+    // 先发送 HEAD 请求，页面正常时再发送 GET
+    pub head_first: bool,
+
+    // 最多读取的响应体大小，单位字节
+    pub max_body_size: usize,
+
+    // HTTP 连接超时和读取超时，单位秒
+    pub connect_timeout: u64,
+    pub read_timeout: u64,
+
+    // 是否对解析出的 IP 进行虚拟主机探测
+    pub vhost: bool,
+
+    // 是否额外请求 robots.txt、sitemap.xml 和页面引用的 JS 来收集域名
+    pub crawl: bool,
 }
 
 impl AppArgs {
@@ -56,6 +139,49 @@ impl AppArgs {
                     .long("output")
                     .help("扫描结果的输出路径"),
             )
+            .arg(
+                Arg::new("format")
+                    .short('f')
+                    .long("format")
+                    .default_value("text")
+                    .value_parser(OutputFormat::NAMES)
+                    .help("输出格式，hosts 为一行一个域名，jsonl 为一行一个 JSON 对象，csv/tsv 为带表头的表格，sqlite 为保留历史的数据库，html/markdown 为扫描结束后生成的报告，by-ip 按 IP、/24 网段和 ASN 聚合域名，dot/graphml/graph-json 为域名、CNAME、IP、ASN 的关系图，默认text"),
+            )
+            .arg(
+                Arg::new("template")
+                    .long("template")
+                    .conflicts_with("format")
+                    .help("自定义输出模板，一行一个结果，例如 '{domain}\\t{ips|join(\",\")}\\t{status|default(\"-\")}'，字段名和 columns 相同，支持 join、default、truncate 过滤器"),
+            )
+            .arg(
+                Arg::new("columns")
+                    .long("columns")
+                    .default_value(Column::DEFAULT)
+                    .help(format!(
+                        "CSV/TSV 输出的列，多个使用英文逗号分隔，多个值的字段使用 {} 拼接，可选: {}",
+                        JOIN_SEPARATOR,
+                        Column::NAMES.join(",")
+                    )),
+            )
+            .arg(
+                Arg::new("diff")
+                    .long("diff")
+                    .help("上一次扫描的结果（JSONL 文件或者 SQLite 数据库），和本次结果对比并输出新增、消失和变化的域名"),
+            )
+            .arg(
+                Arg::new("diff-output")
+                    .long("diff-output")
+                    .requires("diff")
+                    .help("变化报告的输出路径，默认为 {target}.diff.txt"),
+            )
+            .arg(
+                Arg::new("silent")
+                    .short('s')
+                    .long("silent")
+                    .visible_alias("pipe")
+                    .action(ArgAction::SetTrue)
+                    .help("stdout 只输出结果，默认一行一个域名，指定了 text/hosts/jsonl/csv/tsv 格式时按该格式输出，方便接到其他工具的管道里"),
+            )
             .arg(
                 Arg::new("task-count")
                     .short('c')
@@ -82,6 +208,123 @@ impl AppArgs {
                     .action(ArgAction::SetFalse)
                     .help("跳过存在域名的 title 获取，默认开启"),
             )
+            .arg(
+                Arg::new("ports")
+                    .short('p')
+                    .long("ports")
+                    .default_value("80,443")
+                    .help("获取 title 时探测的端口，80 探测 http，443 探测 https，其他端口优先尝试更可能的协议，支持范围，例如 80,443,8080,8000-8010，默认80,443"),
+            )
+            .arg(
+                Arg::new("proxy")
+                    .long("proxy")
+                    .help("HTTP 请求使用的代理，支持 http://、https://、socks5://，例如 socks5://127.0.0.1:1080，DNS 查询不走代理"),
+            )
+            .arg(
+                Arg::new("proxy-auth")
+                    .long("proxy-auth")
+                    .requires("proxy")
+                    .help("代理的认证信息，格式为 user:password，也可以直接写在代理 URL 里"),
+            )
+            .arg(
+                Arg::new("proxy-bypass-internal")
+                    .long("proxy-bypass-internal")
+                    .requires("proxy")
+                    .action(ArgAction::SetTrue)
+                    .help("解析到内网地址的域名直接连接，不走代理"),
+            )
+            .arg(
+                Arg::new("header")
+                    .short('H')
+                    .long("header")
+                    .action(ArgAction::Append)
+                    .help("自定义请求头，格式为 'Name: value'，可以指定多次"),
+            )
+            .arg(
+                Arg::new("cookie")
+                    .long("cookie")
+                    .help("请求时携带的 Cookie，例如 'a=1; b=2'"),
+            )
+            .arg(
+                Arg::new("user-agents")
+                    .long("user-agents")
+                    .help("User-Agent 列表文件，每行一个，每次请求随机选择"),
+            )
+            .arg(
+                Arg::new("head-first")
+                    .long("head-first")
+                    .action(ArgAction::SetTrue)
+                    .help("先发送 HEAD 请求，页面正常时再发送 GET 获取 title"),
+            )
+            .arg(
+                Arg::new("max-body-size")
+                    .long("max-body-size")
+                    .default_value("1048576")
+                    .value_parser(value_parser!(usize))
+                    .help("最多读取的响应体大小，单位字节，默认1MB"),
+            )
+            .arg(
+                Arg::new("connect-timeout")
+                    .long("connect-timeout")
+                    .default_value("5")
+                    .value_parser(value_parser!(u64))
+                    .help("HTTP 连接超时时间，单位秒，默认5"),
+            )
+            .arg(
+                Arg::new("read-timeout")
+                    .long("read-timeout")
+                    .default_value("9")
+                    .value_parser(value_parser!(u64))
+                    .help("HTTP 读取超时时间，单位秒，默认9"),
+            )
+            .arg(
+                Arg::new("vhost")
+                    .long("vhost")
+                    .action(ArgAction::SetTrue)
+                    .help("解析结束后对解析出的 IP 进行虚拟主机探测，使用字典中的候选域名作为 Host"),
+            )
+            .arg(
+                Arg::new("crawl")
+                    .long("crawl")
+                    .action(ArgAction::SetTrue)
+                    .help("额外请求 robots.txt、sitemap.xml 和页面引用的 JS，从中收集子域名"),
+            )
+            .arg(
+                Arg::new("ecs")
+                    .long("ecs")
+                    .help("使用 EDNS Client Subnet 额外查询的子网列表，多个使用英文逗号分隔，例如 1.2.3.0/24,5.6.0.0/16，查询发给给出应答的 NS，协议和普通查询相同（指定 -n 时为 TCP）"),
+            )
+            .arg(
+                Arg::new("provider-data")
+                    .long("provider-data")
+                    .help("CDN/云厂商识别数据的路径，不指定使用内置数据"),
+            )
+            .arg(
+                Arg::new("tech-rules")
+                    .long("tech-rules")
+                    .help("技术栈指纹规则的路径，不指定使用内置规则"),
+            )
+            .arg(
+                Arg::new("skip-cdn")
+                    .long("skip-cdn")
+                    .action(ArgAction::SetTrue)
+                    .help("跳过 CDN 后面域名的 title 获取"),
+            )
+            .arg(
+                Arg::new("asn-db")
+                    .long("asn-db")
+                    .help("本地 ASN 数据库路径（GeoLite2-ASN.mmdb 或兼容格式），文件不存在时不查询 ASN"),
+            )
+            .arg(
+                Arg::new("geo-db")
+                    .long("geo-db")
+                    .help("本地 City/Country 数据库路径（GeoLite2-City.mmdb 或兼容格式），文件不存在时不查询国家"),
+            )
+            .arg(
+                Arg::new("scope")
+                    .long("scope")
+                    .help("扫描范围规则文件，支持 include/exclude 域名规则和 block IP 黑名单"),
+            )
     }
 
     pub fn parse_cli_arguments(&mut self) -> &mut AppArgs {
@@ -124,10 +367,51 @@ impl AppArgs {
             .exit()
         };
 
-        // 取 output
-        self.output_path = matches
-            .get_one::<String>("output")
-            .map_or(format!("{}.out.txt", self.target), |it| it.to_owned());
+        // 取 format 和 output，未指定输出路径时按格式确定扩展名
+        self.format = matches
+            .get_one::<String>("format")
+            .and_then(|it| OutputFormat::from_name(it))
+            .unwrap_or_default();
+        if let Some(template) = matches.get_one::<String>("template") {
+            match Template::parse(template) {
+                Ok(template) => self.template = Some(template),
+                Err(e) => cmd.error(clap::error::ErrorKind::ValueValidation, e).exit(),
+            }
+            self.format = OutputFormat::Template;
+        }
+        self.output_path = matches.get_one::<String>("output").map_or(
+            format!("{}.out.{}", self.target, self.format.extension()),
+            |it| it.to_owned(),
+        );
+
+        // 取 diff 和 diff-output，检查文件是否存在
+        self.diff_path = Self::parse_file_path(
+            &mut cmd,
+            &matches,
+            "diff",
+            "上一次扫描的结果文件不存在，请检查文件路径!",
+        );
+        self.diff_output_path = matches
+            .get_one::<String>("diff-output")
+            .map_or(format!("{}.diff.txt", self.target), |it| it.to_owned());
+
+        // 取 silent，只有显式指定的逐行输出格式才用于 stdout
+        self.silent = matches.get_flag("silent");
+        if self.silent {
+            let explicit = matches.value_source("format") == Some(ValueSource::CommandLine)
+                || self.template.is_some();
+            self.stdout_format = if explicit && self.format.is_line() {
+                Some(self.format)
+            } else {
+                Some(OutputFormat::Hosts)
+            };
+        }
+
+        // 取 columns
+        match self.parse_columns(matches.get_one::<String>("columns").unwrap()) {
+            Ok(columns) => self.columns = columns,
+            Err(e) => cmd.error(clap::error::ErrorKind::ValueValidation, e).exit(),
+        }
 
         // 取 nameserver
         self.nameserver_list = matches
@@ -137,16 +421,255 @@ impl AppArgs {
             .map(|&it| it.to_owned())
             .collect::<Vec<String>>();
 
+        // 取 ports
+        match self.parse_ports(matches.get_one::<String>("ports").unwrap()) {
+            Ok(ports) => self.ports = ports,
+            Err(e) => cmd.error(clap::error::ErrorKind::ValueValidation, e).exit(),
+        }
+
+        // 取 proxy 和 proxy-auth
+        match self.parse_proxy(
+            matches.get_one::<String>("proxy"),
+            matches.get_one::<String>("proxy-auth"),
+        ) {
+            Ok(proxy) => self.proxy = proxy,
+            Err(e) => cmd.error(clap::error::ErrorKind::ValueValidation, e).exit(),
+        }
+        self.proxy_bypass_internal = matches.get_flag("proxy-bypass-internal");
+
+        // 取 header 和 cookie，校验请求头格式
+        self.headers = vec![];
+        for header in matches.get_many::<String>("header").unwrap_or_default() {
+            match Self::parse_header(header) {
+                Ok(header) => self.headers.push(header),
+                Err(e) => cmd.error(clap::error::ErrorKind::ValueValidation, e).exit(),
+            }
+        }
+        self.cookie = matches.get_one::<String>("cookie").map(|it| it.to_owned());
+        if let Some(cookie) = &self.cookie {
+            if HeaderValue::from_str(cookie).is_err() {
+                cmd.error(clap::error::ErrorKind::ValueValidation, "cookie参数有误!")
+                    .exit();
+            }
+        }
+
+        // 取 user-agents，读取 User-Agent 列表
+        self.user_agents = vec![];
+        if let Some(path) = Self::parse_file_path(
+            &mut cmd,
+            &matches,
+            "user-agents",
+            "User-Agent 列表文件不存在，请检查文件路径!",
+        ) {
+            match read_to_string(&path) {
+                Ok(content) => {
+                    self.user_agents = content
+                        .lines()
+                        .map(|it| it.trim())
+                        .filter(|it| !it.is_empty() && !it.starts_with('#'))
+                        .filter(|it| HeaderValue::from_str(it).is_ok())
+                        .map(|it| it.to_owned())
+                        .collect();
+                }
+                Err(e) => cmd
+                    .error(
+                        clap::error::ErrorKind::Io,
+                        format!("读取 User-Agent 列表失败: {:?}", e),
+                    )
+                    .exit(),
+            }
+        }
+
+        // 取 head-first、max-body-size 和超时时间
+        self.head_first = matches.get_flag("head-first");
+        self.max_body_size = matches
+            .get_one::<usize>("max-body-size")
+            .unwrap()
+            .to_owned();
+        self.connect_timeout = matches
+            .get_one::<u64>("connect-timeout")
+            .unwrap()
+            .to_owned();
+        self.read_timeout = matches.get_one::<u64>("read-timeout").unwrap().to_owned();
+
+        // 取 vhost
+        self.vhost = matches.get_flag("vhost");
+
+        // 取 crawl
+        self.crawl = matches.get_flag("crawl");
+
+        // 取 ecs 子网列表
+        match self.parse_ecs(matches.get_one::<String>("ecs")) {
+            Ok(subnets) => self.ecs_subnets = subnets,
+            Err(e) => cmd.error(clap::error::ErrorKind::ValueValidation, e).exit(),
+        }
+
+        // 取 provider-data 和 tech-rules，检查文件是否存在
+        self.provider_data_path = Self::parse_file_path(
+            &mut cmd,
+            &matches,
+            "provider-data",
+            "CDN/云厂商识别数据文件不存在，请检查文件路径!",
+        );
+        self.tech_rules_path = Self::parse_file_path(
+            &mut cmd,
+            &matches,
+            "tech-rules",
+            "技术栈指纹规则文件不存在，请检查文件路径!",
+        );
+
+        // 取 asn-db 和 geo-db，文件不存在时只关闭对应的查询，不在这里报错
+        self.asn_db_path = matches.get_one::<String>("asn-db").map(|it| it.to_owned());
+        self.geo_db_path = matches.get_one::<String>("geo-db").map(|it| it.to_owned());
+
+        // 取 scope，检查文件是否存在
+        self.scope_path = Self::parse_file_path(
+            &mut cmd,
+            &matches,
+            "scope",
+            "扫描范围规则文件不存在，请检查文件路径!",
+        );
+
         // 取 task_count
         self.task_count = matches.get_one::<usize>("task-count").unwrap().to_owned();
 
         // 取 no-wildcard 和 no-title
         self.check_wildcard = matches.get_flag("no-wildcard");
         self.fetch_title = matches.get_flag("no-title");
+        self.skip_cdn = matches.get_flag("skip-cdn");
 
         self
     }
 
+    /// 取文件路径参数，用户提供了但是文件不存在时直接退出
+    fn parse_file_path(
+        cmd: &mut Command,
+        matches: &ArgMatches,
+        id: &str,
+        error: &str,
+    ) -> Option<String> {
+        let path = matches.get_one::<String>(id)?;
+        if !Path::new(path).exists() {
+            cmd.error(clap::error::ErrorKind::ValueValidation, error)
+                .exit();
+        }
+        Some(path.to_owned())
+    }
+
+    /// 解析 columns 参数，列名不能重复
+    fn parse_columns(&self, columns: &str) -> Result<Vec<Column>, String> {
+        let mut result: Vec<Column> = vec![];
+        for name in columns
+            .split(',')
+            .map(|it| it.trim())
+            .filter(|it| !it.is_empty())
+        {
+            match Column::from_name(name) {
+                Some(column) if !result.contains(&column) => result.push(column),
+                Some(_) => return Err(format!("columns参数有误，重复的列: {}", name)),
+                None => return Err(format!("columns参数有误，未知的列: {}", name)),
+            }
+        }
+
+        if result.is_empty() {
+            return Err("columns参数有误，至少指定一列!".to_owned());
+        }
+        Ok(result)
+    }
+
+    /// 解析 ports 参数，支持单个端口和 a-b 形式的范围
+    fn parse_ports(&self, ports: &str) -> Result<Vec<u16>, String> {
+        let mut result: Vec<u16> = vec![];
+        for item in ports
+            .split(',')
+            .map(|it| it.trim())
+            .filter(|it| !it.is_empty())
+        {
+            let range = match item.split_once('-') {
+                Some((first, second)) => match (first.parse::<u16>(), second.parse::<u16>()) {
+                    (Ok(first), Ok(second)) if first != 0 && first <= second => first..=second,
+                    _ => return Err(format!("ports参数有误，无效的端口范围: {}", item)),
+                },
+                None => match item.parse::<u16>() {
+                    Ok(port) if port != 0 => port..=port,
+                    _ => return Err(format!("ports参数有误，无效的端口: {}", item)),
+                },
+            };
+            for port in range {
+                if !result.contains(&port) {
+                    result.push(port);
+                }
+            }
+        }
+
+        if result.is_empty() {
+            return Err("ports参数有误，至少指定一个端口!".to_owned());
+        }
+        Ok(result)
+    }
+
+    /// 解析自定义请求头，格式为 Name: value
+    fn parse_header(header: &str) -> Result<(String, String), String> {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("header参数有误，格式为 'Name: value': {}", header))?;
+        let (name, value) = (name.trim(), value.trim());
+        if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err()
+        {
+            return Err(format!("header参数有误，无效的请求头: {}", header));
+        }
+        Ok((name.to_owned(), value.to_owned()))
+    }
+
+    /// 解析 proxy 参数，把 proxy-auth 中的认证信息合并到 URL 里
+    fn parse_proxy(
+        &self,
+        proxy: Option<&String>,
+        auth: Option<&String>,
+    ) -> Result<Option<Url>, String> {
+        let proxy = match proxy {
+            Some(proxy) => proxy,
+            None => return Ok(None),
+        };
+
+        let mut url =
+            Url::parse(proxy).map_err(|_| format!("proxy参数有误，无效的代理地址: {}", proxy))?;
+        if !["http", "https", "socks5", "socks5h"].contains(&url.scheme())
+            || url.host_str().is_none()
+        {
+            return Err(format!("proxy参数有误，不支持的代理地址: {}", proxy));
+        }
+
+        if let Some(auth) = auth {
+            let (username, password) = auth
+                .split_once(':')
+                .ok_or_else(|| "proxy-auth参数有误，格式为 user:password".to_owned())?;
+            if url.set_username(username).is_err() || url.set_password(Some(password)).is_err() {
+                return Err("proxy-auth参数有误，无法设置认证信息".to_owned());
+            }
+        }
+
+        Ok(Some(url))
+    }
+
+    /// 解析 ecs 参数
+    fn parse_ecs(&self, ecs: Option<&String>) -> Result<Vec<IpNet>, String> {
+        let mut subnets = vec![];
+        if let Some(ecs) = ecs {
+            for item in ecs
+                .split(',')
+                .map(|it| it.trim())
+                .filter(|it| !it.is_empty())
+            {
+                match item.parse::<IpNet>() {
+                    Ok(subnet) => subnets.push(subnet.trunc()),
+                    Err(_) => return Err(format!("ecs参数有误，无效的子网: {}", item)),
+                }
+            }
+        }
+        Ok(subnets)
+    }
+
     /// 解析 length 参数
     fn parse_length(&self, length: Option<&String>) -> Result<(usize, usize), &str> {
         if length.is_none() {
@@ -169,7 +692,6 @@ impl AppArgs {
                     length_part[0].parse::<usize>(),
                     length_part[1].parse::<usize>(),
                 ) {
-                    println!("first: {}, second: {}", first, second);
                     if first != 0 && second != 0 && second > first {
                         return Ok((first, second));
                     }
@@ -1,3 +1,14 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+use async_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+
+/// vhost 探测得到的结果的来源
+pub const VHOST_SOURCE: &str = "vhost";
+
 /// 引擎的状态，只有三个
 #[derive(Debug, PartialEq)]
 pub enum EngineStatus {
@@ -6,29 +17,255 @@ pub enum EngineStatus {
     Stop,
 }
 
+/// 解析过程中发现的新域名，放回解析队列
+#[derive(Debug)]
+pub struct DiscoveredTask {
+    pub task: String,
+    pub source: String,
+}
+
 /// App Context
 /// 存储各个引擎的状态，以及 app args
 pub struct AppContext {
     pub task_builder_status: EngineStatus,
     pub resolver_status: Vec<EngineStatus>,
-    pub saver_status: EngineStatus,
+    // 未开启 vhost 探测时为 Stop
+    pub vhost_status: EngineStatus,
+
+    // 新发现域名的任务通道
+    pub discovered_tx: Sender<DiscoveredTask>,
+    pub discovered_rx: Receiver<DiscoveredTask>,
+    // 已经解析出结果的域名，用于结果去重
+    pub found_domains: HashSet<String>,
+    // 已经放回队列的新域名
+    pub discovered_domains: HashSet<String>,
+    // vhost 探测的目标 IP
+    pub vhost_ips: BTreeSet<String>,
+    // 已经解析出的域名和它的 IP，vhost 探测时不再用域名请求它自己的 IP
+    pub resolved_ips: HashMap<String, BTreeSet<String>>,
 }
 
 impl AppContext {
     pub fn new() -> Self {
+        let (discovered_tx, discovered_rx) = async_channel::unbounded::<DiscoveredTask>();
         Self {
             task_builder_status: EngineStatus::Init,
             resolver_status: vec![],
-            saver_status: EngineStatus::Init,
+            vhost_status: EngineStatus::Stop,
+            discovered_tx,
+            discovered_rx,
+            found_domains: HashSet::new(),
+            discovered_domains: HashSet::new(),
+            vhost_ips: BTreeSet::new(),
+            resolved_ips: HashMap::new(),
+        }
+    }
+
+    /// 把新发现的域名放回解析队列，已经解析过或者已经在队列里的域名返回 false
+    pub fn discover(&mut self, task: &str, domain: &str, source: &str) -> bool {
+        if self.found_domains.contains(domain) || !self.discovered_domains.insert(domain.to_owned())
+        {
+            return false;
+        }
+        self.discovered_tx
+            .try_send(DiscoveredTask {
+                task: task.to_owned(),
+                source: source.to_owned(),
+            })
+            .is_ok()
+    }
+}
+
+/// 单条 DNS 记录，带上应答时的 TTL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub record_type: String,
+    pub value: String,
+    pub ttl: u32,
+}
+
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.record_type, self.value, self.ttl)
+    }
+}
+
+/// IP 的 ASN、组织和国家信息
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct IpInfo {
+    pub asn: Option<u32>,
+    pub org: Option<String>,
+    pub country: Option<String>,
+}
+
+impl fmt::Display for IpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(asn) = self.asn {
+            parts.push(format!("AS{}", asn));
+        }
+        if let Some(org) = &self.org {
+            parts.push(org.to_owned());
+        }
+        if let Some(country) = &self.country {
+            parts.push(country.to_owned());
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// 跳转链中的一跳
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectHop {
+    pub code: u16,
+    pub location: String,
+}
+
+/// HTTPS 叶子证书信息
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CertInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: String,
+    pub not_after: String,
+    pub expired: bool,
+    pub self_signed: bool,
+}
+
+impl fmt::Display for CertInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} issued by {:?} {:?} valid {} ~ {}",
+            self.subject, self.issuer, self.sans, self.not_before, self.not_after
+        )?;
+        if self.expired {
+            write!(f, " expired")?;
+        }
+        if self.self_signed {
+            write!(f, " self-signed")?;
+        }
+        Ok(())
+    }
+}
+
+/// 单个 URL 的 HTTP 探测结果，请求失败时 code 为空，error 为失败原因
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HttpProbe {
+    pub url: String,
+    // 最后一次请求的 URL，没有跳转时和 url 相同
+    pub final_url: String,
+    // 最后一次请求的状态码
+    pub code: Option<u16>,
+    pub title: Option<String>,
+    pub error: Option<String>,
+    // 跳转链，每一跳的状态码和 Location
+    pub redirects: Vec<RedirectHop>,
+    // 跳转到目标主域之外的地址（例如 SSO 登录页），不会继续请求
+    pub offsite_redirect: Option<String>,
+    // HTTPS 的证书信息
+    pub certificate: Option<CertInfo>,
+    // Server 和 X-Powered-By 响应头
+    pub server: Option<String>,
+    pub powered_by: Option<String>,
+    // 响应体长度，优先使用 Content-Length
+    pub content_length: Option<u64>,
+    // 响应体的 sha256，用于对相同的默认页面去重
+    pub body_hash: Option<String>,
+    // favicon 的 mmh3 hash，和 Shodan 的 http.favicon.hash 一致
+    pub favicon_hash: Option<i32>,
+    // 匹配到的技术栈
+    pub technologies: Vec<String>,
+    // 响应中提到的目标主域下的域名和来源
+    pub harvested: BTreeMap<String, String>,
+}
+
+impl fmt::Display for HttpProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(f, "{} error: {}", self.url, error)?,
+            None => write!(
+                f,
+                "{} {} {:?}",
+                self.url,
+                self.code.unwrap_or(0),
+                self.title.as_deref().unwrap_or_default()
+            )?,
+        }
+        for hop in self.redirects.iter() {
+            write!(f, " -> {} {}", hop.code, hop.location)?;
+        }
+        if let Some(offsite) = &self.offsite_redirect {
+            write!(f, " offsite: {}", offsite)?;
+        }
+        if let Some(server) = &self.server {
+            write!(f, " server: {:?}", server)?;
         }
+        if let Some(powered_by) = &self.powered_by {
+            write!(f, " powered-by: {:?}", powered_by)?;
+        }
+        if let Some(content_length) = self.content_length {
+            write!(f, " length: {}", content_length)?;
+        }
+        if let Some(body_hash) = &self.body_hash {
+            write!(f, " hash: {}", body_hash)?;
+        }
+        if let Some(favicon_hash) = self.favicon_hash {
+            write!(f, " favicon: {}", favicon_hash)?;
+        }
+        if !self.technologies.is_empty() {
+            write!(f, " tech: {:?}", self.technologies)?;
+        }
+        if let Some(certificate) = &self.certificate {
+            write!(f, " cert: {}", certificate)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ResolveResult {
     pub domain: String,
-    pub title: Option<String>,
-    pub code: Option<u16>,
+    // 80 端口的 http 和 443 端口的 https 失败时也有一条结果，记录失败原因，其他端口只记录有响应的协议
+    pub http: Vec<HttpProbe>,
+    pub https: Vec<HttpProbe>,
     pub ip: Vec<String>,
     pub cname: Vec<String>,
+    // 应答中的全部记录，包含 TTL
+    pub records: Vec<DnsRecord>,
+    // 给出应答的 NS
+    pub nameserver: Option<String>,
+    // 查询耗时，单位毫秒
+    pub latency_ms: u64,
+    // DNS 响应码，例如 NOERROR / NXDOMAIN
+    pub rcode: Option<String>,
+    // 开启 ECS 时，每个 IP 是由哪些子网的查询得到的
+    pub ecs: BTreeMap<String, Vec<String>>,
+    // 新发现域名的来源，字典和枚举生成的任务为空
+    pub source: Option<String>,
+    // 识别出的 CDN / 云厂商
+    pub provider: Option<String>,
+    // 厂商是否为 CDN
+    pub cdn: bool,
+    // 每个 IP 的 ASN / 组织 / 国家信息，未提供 mmdb 时为空
+    pub ip_info: BTreeMap<String, IpInfo>,
+}
+
+impl ResolveResult {
+    /// 是否是 vhost 探测得到的结果
+    pub fn is_vhost(&self) -> bool {
+        self.source.as_deref() == Some(VHOST_SOURCE)
+    }
+
+    /// 合并同一个域名在其他 IP 上的 vhost 探测结果，IP 和探测结果追加在后面，DNS 相关的字段不变
+    pub fn merge_vhost(&mut self, other: &ResolveResult) {
+        for ip in other.ip.iter() {
+            if !self.ip.contains(ip) {
+                self.ip.push(ip.to_owned());
+            }
+        }
+        self.http.extend(other.http.iter().cloned());
+        self.https.extend(other.https.iter().cloned());
+    }
 }
@@ -1,7 +1,9 @@
 pub mod resolver;
 mod saver;
 mod task_builder;
+mod vhost;
 
 pub use resolver::{build_resolver, check_wildcard, resolver};
 pub use saver::saver;
 pub use task_builder::task_builder;
+pub use vhost::vhost;
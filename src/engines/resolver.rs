@@ -1,29 +1,53 @@
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_channel::{Receiver, Sender};
+use ipnet::IpNet;
+use log::{debug, info};
 use rand::{distributions::Alphanumeric, Rng};
-use regex::Regex;
-use reqwest::{Client, ClientBuilder};
-use tokio::sync::Mutex;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+    sync::Mutex,
+};
 use trust_dns_resolver::{
     config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts},
-    error::ResolveError,
+    error::{ResolveError, ResolveErrorKind},
     name_server::{GenericConnection, GenericConnectionProvider, TokioRuntime},
-    proto::rr::RecordType,
+    proto::{
+        op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
+        rr::{rdata::opt::EdnsOption, Name, Record, RecordType},
+    },
     AsyncResolver, TokioAsyncResolver,
 };
 
 use crate::{
     args::AppArgs,
-    context::{AppContext, EngineStatus, ResolveResult},
+    context::{AppContext, DnsRecord, EngineStatus, ResolveResult},
+    enrich::Enricher,
+    http::{build_http_client, http_worker, is_internal_ip, Harvester},
+    scope::Scope,
 };
 
+type DnsResolver = AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>;
+
+/// 用户提供的 NS 使用的协议，内置的 NS 使用 UDP
+const USER_NAMESERVER_PROTOCOL: Protocol = Protocol::Tcp;
+
+/// ECS 查询的超时时间
+const ECS_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub async fn resolver(
     task_channel: Receiver<String>,
     result_channel: Sender<ResolveResult>,
     idx: usize,
     app_args: Arc<AppArgs>,
     app_context: Arc<Mutex<AppContext>>,
+    enricher: Arc<Enricher>,
+    scope: Arc<Scope>,
 ) {
     // println!("Resolver engine {idx} start!");
     let mut guard = app_context.lock().await;
@@ -33,51 +57,137 @@ pub async fn resolver(
     // 目标
     let target = &app_args.target;
 
-    // 构建 dns resolver
-    let resolver = build_resolver(&app_args.nameserver_list).expect("Build DNS Resolver Error!");
+    // 构建 dns resolver，每个 NS 一个
+    let resolvers = build_resolvers(&app_args.nameserver_list).expect("Build DNS Resolver Error!");
+    if resolvers.is_empty() {
+        eprintln!("No available nameserver, resolver engine {idx} exit.");
+        app_context.lock().await.resolver_status[idx] = EngineStatus::Stop;
+        return;
+    }
+    // ECS 查询和普通查询使用同样的协议
+    let ecs_protocol = if app_args.nameserver_list.is_empty() {
+        Protocol::Udp
+    } else {
+        USER_NAMESERVER_PROTOCOL
+    };
+    // 已经查询过的数量，用于在多个 NS 之间轮换
+    let mut query_count: usize = 0;
 
-    // 构建 http client
-    let http_client = ClientBuilder::new()
-        .timeout(Duration::from_secs(9))
-        .danger_accept_invalid_certs(true)
-        .build()
-        .unwrap();
+    // 构建 http client，配置了代理并且内网地址不走代理时，额外构建一个直连的 client
+    let http_client = build_http_client(&app_args, true).expect("Build HTTP Client Error!");
+    let direct_client = if app_args.proxy.is_some() && app_args.proxy_bypass_internal {
+        Some(build_http_client(&app_args, false).expect("Build HTTP Client Error!"))
+    } else {
+        None
+    };
+    // 从响应中收集域名的正则只和目标有关，每个 resolver 只构建一次
+    let harvester = Harvester::new(target);
 
-    // 获取网页标题的正则
-    let title_regex = Regex::new(r"<title.*?>(?P<title>.+?)</title>").unwrap();
+    // 新发现域名的任务通道
+    let discovered_rx = app_context.lock().await.discovered_rx.clone();
 
     loop {
-        let task = task_channel.try_recv();
-        if task.is_err() {
-            if app_context.lock().await.task_builder_status == EngineStatus::Stop {
-                break;
-            }
-            tokio::time::sleep(Duration::from_millis(200)).await;
-            continue;
-        }
+        // 优先处理解析过程中新发现的域名
+        let (task, source) = match discovered_rx.try_recv() {
+            Ok(discovered) => (discovered.task, Some(discovered.source)),
+            Err(_) => match task_channel.try_recv() {
+                Ok(task) => (task, None),
+                Err(_) => {
+                    if app_context.lock().await.task_builder_status == EngineStatus::Stop {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    continue;
+                }
+            },
+        };
 
         // 开始解析域名
-        let task = task.unwrap();
         let domain = format!("{}.{}", task, target);
-        let (cname_list, ip_list) = dns_worker(domain.as_str(), &resolver).await;
+        let mut res = dns_worker(domain.as_str(), &resolvers, idx + query_count).await;
+        query_count += 1;
+        res.source = source;
 
-        // 如果有结果，并且配置了获取 title 就发起请求
-        let mut status_code: Option<u16> = None;
-        let mut title: Option<String> = None;
-        if !ip_list.is_empty() && app_args.fetch_title {
-            (status_code, title) = http_worker(domain.as_str(), &title_regex, &http_client).await;
+        // 同一个域名只处理一次，新发现的域名可能和字典里的重复
+        let resolved = !res.cname.is_empty() || !res.ip.is_empty();
+        if resolved
+            && !app_context
+                .lock()
+                .await
+                .found_domains
+                .insert(domain.clone())
+        {
+            continue;
         }
 
-        // 把解析结果扔到队列里
-        if !cname_list.is_empty() || !ip_list.is_empty() {
-            let res = ResolveResult {
-                domain,
-                title,
-                code: status_code,
-                ip: ip_list,
-                cname: cname_list,
+        // 配置了 ECS 子网时，用不同子网再查一遍，合并不同地区的应答
+        if !app_args.ecs_subnets.is_empty() && (!res.ip.is_empty() || !res.cname.is_empty()) {
+            ecs_worker(&mut res, &app_args.ecs_subnets, ecs_protocol).await;
+        }
+
+        // 识别 CDN / 云厂商
+        enricher.enrich(&mut res);
+
+        // 如果有结果，并且配置了获取 title 就发起请求，开启 skip-cdn 时跳过 CDN 后面的域名
+        // 解析到黑名单 IP 的域名不能发起任何请求
+        let skip_http =
+            (app_args.skip_cdn && res.cdn) || res.ip.iter().any(|ip| scope.is_blocked(ip));
+        if !res.ip.is_empty() && app_args.fetch_title && !skip_http {
+            let client = match &direct_client {
+                Some(direct_client) if res.ip.iter().all(|ip| is_internal_ip(ip)) => direct_client,
+                _ => &http_client,
             };
-            println!("Found: {:?}", res);
+            (res.http, res.https) = http_worker(
+                domain.as_str(),
+                client,
+                &app_args,
+                &enricher,
+                &harvester,
+                &scope,
+            )
+            .await;
+        }
+
+        // 记录 vhost 探测的目标 IP，黑名单中的 IP 和 CDN 的 IP 不探测
+        if app_args.vhost {
+            let mut guard = app_context.lock().await;
+            if !(app_args.skip_cdn && res.cdn) {
+                let ip_list = res.ip.iter().filter(|ip| !scope.is_blocked(ip)).cloned();
+                guard.vhost_ips.extend(ip_list);
+            }
+            if !res.ip.is_empty() {
+                guard
+                    .resolved_ips
+                    .entry(domain.clone())
+                    .or_default()
+                    .extend(res.ip.iter().cloned());
+            }
+        }
+
+        // 证书中目标主域下的新域名放回解析队列
+        let sans = res
+            .https
+            .iter()
+            .filter_map(|it| it.certificate.as_ref())
+            .flat_map(|it| it.sans.iter().cloned())
+            .collect::<Vec<_>>();
+        discover_domains(&sans, "certificate", target, &scope, &app_context).await;
+
+        // 响应中提到的目标主域下的域名放回解析队列，来源按收集的位置区分
+        let harvested = res
+            .http
+            .iter()
+            .chain(res.https.iter())
+            .flat_map(|it| it.harvested.iter())
+            .map(|(domain, source)| (domain.to_owned(), source.to_owned()))
+            .collect::<Vec<_>>();
+        for (domain, source) in harvested {
+            discover_domains(&[domain], &source, target, &scope, &app_context).await;
+        }
+
+        // 把解析结果扔到队列里
+        if resolved {
+            info!("Found: {} {:?}", res.domain, res.ip);
             if let Err(e) = result_channel.send(res).await {
                 eprintln!("Error put task to result_channel, error: {:?}", e);
             }
@@ -88,52 +198,105 @@ pub async fn resolver(
     // println!("Resolver engine {idx} finished!");
 }
 
-/// 构建 DNS Resolver
-pub fn build_resolver(
-    nameservers: &Vec<String>,
-) -> Result<AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>, ResolveError>
-{
-    let resolve_config = if nameservers.is_empty() {
+/// 把目标主域下的新域名放回解析队列，不在扫描范围内的丢弃
+async fn discover_domains(
+    names: &[String],
+    source: &str,
+    target: &str,
+    scope: &Scope,
+    app_context: &Arc<Mutex<AppContext>>,
+) {
+    let suffix = format!(".{}", target.to_lowercase());
+    let mut guard = app_context.lock().await;
+    for name in names {
+        // 通配符证书 *.a.example.com 取 a.example.com
+        let domain = name
+            .trim()
+            .trim_start_matches("*.")
+            .trim_end_matches('.')
+            .to_lowercase();
+        let task = match domain.strip_suffix(&suffix) {
+            Some(task) if !task.is_empty() => task,
+            _ => continue,
+        };
+        if scope.contains_domain(&domain) && guard.discover(task, &domain, source) {
+            info!("Discovered: {} from {}", domain, source);
+        }
+    }
+}
+
+/// 构建 NS 配置列表，未提供 NS 时使用内置的 NS 列表
+fn build_nameserver_configs(nameservers: &Vec<String>) -> Vec<NameServerConfig> {
+    if nameservers.is_empty() {
         // 内置使用多个 DNS，防止一个出现超时全部卡死的情况
-        // 1. 添加国内 DNS
-        let mut config = ResolverConfig::new();
-        config.add_name_server(NameServerConfig::new("223.5.5.5:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-        config.add_name_server(NameServerConfig::new("223.6.6.6:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-        // tencent
-        config.add_name_server(NameServerConfig::new("119.29.29.29:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-        // baidu
-        config.add_name_server(NameServerConfig::new("180.76.76.76:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-
-        // 2. 添加 Google DNS
-        config.add_name_server(NameServerConfig::new("8.8.8.8:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-        config.add_name_server(NameServerConfig::new("8.8.4.4:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-
-        // 3. 添加 Cloudflare DNS
-        config.add_name_server(NameServerConfig::new("1.1.1.1:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-
-        // 兜底
-        config.add_name_server(NameServerConfig::new("114.114.114.114:53".parse::<SocketAddr>().unwrap(), Protocol::Udp));
-
-        config
+        let udp =
+            |addr: &str| NameServerConfig::new(addr.parse::<SocketAddr>().unwrap(), Protocol::Udp);
+        vec![
+            // 1. 添加国内 DNS
+            udp("223.5.5.5:53"),
+            udp("223.6.6.6:53"),
+            // tencent
+            udp("119.29.29.29:53"),
+            // baidu
+            udp("180.76.76.76:53"),
+            // 2. 添加 Google DNS
+            udp("8.8.8.8:53"),
+            udp("8.8.4.4:53"),
+            // 3. 添加 Cloudflare DNS
+            udp("1.1.1.1:53"),
+            // 兜底
+            udp("114.114.114.114:53"),
+        ]
     } else {
         // 使用用户提供的 NS IP
-        let mut resolve_config = ResolverConfig::new();
+        let mut configs = vec![];
         for ns_ip in nameservers {
             match format!("{}:53", ns_ip).parse::<SocketAddr>() {
-                Ok(ip) => resolve_config.add_name_server(NameServerConfig::new(ip, Protocol::Tcp)),
+                Ok(ip) => configs.push(NameServerConfig::new(ip, USER_NAMESERVER_PROTOCOL)),
                 Err(e) => {
                     eprintln!("Invalid Nameserver IP {}, error: {:?}, skip.", ns_ip, e);
                     continue;
                 }
             };
         }
-        resolve_config
-    };
+        configs
+    }
+}
 
+fn build_resolver_opts() -> ResolverOpts {
     // 查询超时时间改成1秒
     let mut opts = ResolverOpts::default();
     opts.timeout = Duration::from_secs(1);
-    TokioAsyncResolver::tokio(resolve_config, opts)
+    opts
+}
+
+/// 构建 DNS Resolver
+pub fn build_resolver(
+    nameservers: &Vec<String>,
+) -> Result<AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>, ResolveError>
+{
+    let mut resolve_config = ResolverConfig::new();
+    for config in build_nameserver_configs(nameservers) {
+        resolve_config.add_name_server(config);
+    }
+    TokioAsyncResolver::tokio(resolve_config, build_resolver_opts())
+}
+
+/// 为每个 NS 单独构建一个 Resolver，这样才能知道应答是哪个 NS 给出的
+pub fn build_resolvers(
+    nameservers: &Vec<String>,
+) -> Result<Vec<(String, DnsResolver)>, ResolveError> {
+    let mut resolvers = vec![];
+    for config in build_nameserver_configs(nameservers) {
+        let name = config.socket_addr.to_string();
+        let mut resolve_config = ResolverConfig::new();
+        resolve_config.add_name_server(config);
+        resolvers.push((
+            name,
+            TokioAsyncResolver::tokio(resolve_config, build_resolver_opts())?,
+        ));
+    }
+    Ok(resolvers)
 }
 
 /// 检查泛解析
@@ -141,7 +304,7 @@ pub async fn check_wildcard(
     target: &str,
     resolver: &AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>,
 ) -> Result<(), String> {
-    println!("start checking wildcard resolve.");
+    info!("start checking wildcard resolve.");
     let mut wildcards: Vec<String> = vec!["thisdomainneverexist".into()];
     let rand_subdomain = rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -149,7 +312,7 @@ pub async fn check_wildcard(
         .map(char::from)
         .collect();
     wildcards.push(rand_subdomain);
-    println!("domain list: {:?}", wildcards);
+    info!("domain list: {:?}", wildcards);
 
     for wildcard in wildcards {
         let full_domain = format!("{}.{}", wildcard, target);
@@ -164,52 +327,218 @@ pub async fn check_wildcard(
 }
 
 /// 解析域名到 IP
+/// 从 offset 指定的 NS 开始查询，查询出错（超时等）时换下一个 NS，
+/// 拿到明确的应答（包括 NXDOMAIN）后停止
 async fn dns_worker(
     target: &str,
-    resolver: &AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>,
-) -> (Vec<String>, Vec<String>) {
-    let mut cname_list: Vec<String> = vec![];
-    let mut ip_list: Vec<String> = vec![];
+    resolvers: &[(String, DnsResolver)],
+    offset: usize,
+) -> ResolveResult {
+    let mut result = ResolveResult {
+        domain: target.to_owned(),
+        ..Default::default()
+    };
 
-    if let Ok(resp) = resolver.lookup(target, RecordType::CNAME).await {
-        cname_list.extend(resp.iter().map(|it| it.to_string()));
-    }
+    for i in 0..resolvers.len() {
+        let (nameserver, resolver) = &resolvers[(offset + i) % resolvers.len()];
+        let start = Instant::now();
+
+        let mut records: Vec<DnsRecord> = vec![];
+        let response_code = match resolver.lookup_ip(target).await {
+            Ok(resp) => {
+                result.ip = resp.iter().map(|it| it.to_string()).collect();
+                records.extend(resp.as_lookup().record_iter().filter_map(to_dns_record));
+                ResponseCode::NoError
+            }
+            Err(e) => match e.kind() {
+                ResolveErrorKind::NoRecordsFound { response_code, .. } => *response_code,
+                // 超时或者其他错误，换下一个 NS 再试
+                _ => continue,
+            },
+        };
 
-    if let Ok(resp) = resolver.lookup_ip(target).await {
-        ip_list.extend(resp.iter().map(|it| it.to_string()));
+        if let Ok(resp) = resolver.lookup(target, RecordType::CNAME).await {
+            result.cname = resp.iter().map(|it| it.to_string()).collect();
+            records.extend(resp.record_iter().filter_map(to_dns_record));
+        }
+
+        for record in records {
+            if !result.records.contains(&record) {
+                result.records.push(record);
+            }
+        }
+        result.latency_ms = start.elapsed().as_millis() as u64;
+        result.nameserver = Some(nameserver.to_owned());
+        result.rcode = Some(format!("{:?}", response_code).to_uppercase());
+        break;
     }
 
-    (cname_list, ip_list)
+    result
 }
 
-/// 获取 HTTP 状态码和网页 title
-async fn http_worker(
-    target: &str,
-    re: &Regex,
-    http_client: &Client,
-) -> (Option<u16>, Option<String>) {
-    let urls = [format!("http://{}", target), format!("https://{}", target)];
-    for url in urls.iter() {
-        match http_client.get(url).send().await {
-            Ok(resp) => {
-                let code = resp.status().as_u16();
-                let html = resp.text().await.unwrap();
-                let mut title: Option<String> = None;
-                if let Some(caps) = re.captures(&html) {
-                    let _title = caps.name("title").map_or("", |m| m.as_str()).to_owned();
-                    title = Some(_title);
+fn to_dns_record(record: &Record) -> Option<DnsRecord> {
+    record.data().map(|data| DnsRecord {
+        record_type: record.record_type().to_string(),
+        value: data.to_string(),
+        ttl: record.ttl(),
+    })
+}
+
+/// 对每个 ECS 子网单独查询一次，把新出现的 IP 合并到结果中，并记录 IP 来自哪个子网
+/// 查询发给之前给出应答的 NS，注意部分公共 DNS（例如 1.1.1.1）会忽略 ECS
+async fn ecs_worker(result: &mut ResolveResult, subnets: &[IpNet], protocol: Protocol) {
+    let nameserver = match result
+        .nameserver
+        .as_ref()
+        .map(|it| it.parse::<SocketAddr>())
+    {
+        Some(Ok(nameserver)) => nameserver,
+        _ => return,
+    };
+
+    for subnet in subnets {
+        for record_type in [RecordType::A, RecordType::AAAA] {
+            match ecs_query(&result.domain, record_type, subnet, nameserver, protocol).await {
+                Ok(ip_list) => {
+                    for ip in ip_list {
+                        if !result.ip.contains(&ip) {
+                            result.ip.push(ip.clone());
+                        }
+                        let tags = result.ecs.entry(ip).or_default();
+                        if !tags.contains(&subnet.to_string()) {
+                            tags.push(subnet.to_string());
+                        }
+                    }
                 }
-                return (Some(code), title);
-            }
-            Err(e) => {
-                eprintln!(
-                    "Fetch HTTP status_code and title error. error: {:?}. Try HTTPS...",
-                    e
-                );
-                continue;
+                Err(e) => debug!(
+                    "ECS query error, domain: {}, subnet: {}, error: {}",
+                    result.domain, subnet, e
+                ),
             }
         }
     }
+}
+
+/// 手动构造一个带 ECS 选项的查询报文，按照 protocol 通过 UDP 或者 TCP 发送给指定的 NS
+async fn ecs_query(
+    domain: &str,
+    record_type: RecordType,
+    subnet: &IpNet,
+    nameserver: SocketAddr,
+    protocol: Protocol,
+) -> Result<Vec<String>, String> {
+    let name = Name::from_ascii(domain).map_err(|e| format!("{:?}", e))?;
+    let id: u16 = rand::random();
 
-    (None, None)
+    let mut edns = Edns::new();
+    edns.set_max_payload(1232);
+    edns.options_mut().insert(build_ecs_option(subnet));
+
+    let mut message = Message::new();
+    message
+        .set_id(id)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(name, record_type))
+        .set_edns(edns);
+    let request = message.to_vec().map_err(|e| format!("{:?}", e))?;
+
+    let response = match protocol {
+        Protocol::Tcp => query_tcp(&request, nameserver).await?,
+        _ => query_udp(&request, nameserver).await?,
+    };
+    let response = Message::from_vec(&response).map_err(|e| format!("{:?}", e))?;
+    if response.id() != id {
+        return Err("response id mismatch".to_owned());
+    }
+
+    Ok(response
+        .answers()
+        .iter()
+        .filter(|it| it.record_type() == record_type)
+        .filter_map(|it| it.data().map(|data| data.to_string()))
+        .collect())
+}
+
+async fn query_udp(request: &[u8], nameserver: SocketAddr) -> Result<Vec<u8>, String> {
+    let bind_addr = if nameserver.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_addr)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    socket
+        .send_to(request, nameserver)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+
+    let mut buffer = [0u8; 4096];
+    let size = tokio::time::timeout(ECS_TIMEOUT, socket.recv(&mut buffer))
+        .await
+        .map_err(|_| "timeout".to_owned())?
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(buffer[..size].to_vec())
+}
+
+/// TCP 的报文前面带两个字节的长度
+async fn query_tcp(request: &[u8], nameserver: SocketAddr) -> Result<Vec<u8>, String> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(nameserver).await?;
+        stream
+            .write_all(&(request.len() as u16).to_be_bytes())
+            .await?;
+        stream.write_all(request).await?;
+
+        let mut length = [0u8; 2];
+        stream.read_exact(&mut length).await?;
+        let mut buffer = vec![0u8; u16::from_be_bytes(length) as usize];
+        stream.read_exact(&mut buffer).await?;
+        Ok::<_, std::io::Error>(buffer)
+    };
+    tokio::time::timeout(ECS_TIMEOUT, exchange)
+        .await
+        .map_err(|_| "timeout".to_owned())?
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// 按照 RFC 7871 构造 Client Subnet 选项，地址只保留前缀长度覆盖的字节
+fn build_ecs_option(subnet: &IpNet) -> EdnsOption {
+    let (family, address): (u16, Vec<u8>) = match subnet.addr() {
+        IpAddr::V4(ip) => (1, ip.octets().to_vec()),
+        IpAddr::V6(ip) => (2, ip.octets().to_vec()),
+    };
+    let prefix_len = subnet.prefix_len();
+
+    let mut data = family.to_be_bytes().to_vec();
+    data.push(prefix_len);
+    data.push(0);
+    data.extend_from_slice(&address[..(prefix_len as usize).div_ceil(8)]);
+    EdnsOption::Unknown(8, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ecs_data(subnet: &str) -> Vec<u8> {
+        match build_ecs_option(&subnet.parse::<IpNet>().unwrap()) {
+            EdnsOption::Unknown(8, data) => data,
+            other => panic!("unexpected option: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ecs_option_keeps_prefix_bytes_only() {
+        // family, source prefix length, scope prefix length, address
+        assert_eq!(ecs_data("1.2.3.0/24"), vec![0, 1, 24, 0, 1, 2, 3]);
+        assert_eq!(ecs_data("10.20.0.0/12"), vec![0, 1, 12, 0, 10, 20]);
+        assert_eq!(ecs_data("0.0.0.0/0"), vec![0, 1, 0, 0]);
+        assert_eq!(
+            ecs_data("2001:db8::/32"),
+            vec![0, 2, 32, 0, 0x20, 0x01, 0x0d, 0xb8]
+        );
+    }
 }
@@ -1,54 +1,88 @@
-use std::{sync::Arc, time::Duration};
+use std::{process::exit, sync::Arc, time::Duration};
 
 use async_channel::Receiver;
-use tokio::io::AsyncWriteExt;
-use tokio::{fs::File, sync::Mutex};
+use log::info;
+use tokio::sync::Mutex;
 
 use crate::{
     args::AppArgs,
-    context::{AppContext, ResolveResult},
+    context::{AppContext, EngineStatus, ResolveResult},
+    output::{Differ, Writer},
 };
 
 pub async fn saver(
     rx: Receiver<ResolveResult>,
     app_context: Arc<Mutex<AppContext>>,
     app_args: Arc<AppArgs>,
+    mut differ: Option<Differ>,
 ) {
-    println!("saver engine start.");
-    let output = &app_args.output_path;
-    let mut output_file = File::create(output).await.unwrap();
+    info!("saver engine start.");
+    let mut writer = match Writer::open(&app_args).await {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(-1);
+        }
+    };
+    let mut stdout = match Writer::open_stdout(&app_args).await {
+        Ok(stdout) => stdout,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(-1);
+        }
+    };
 
     loop {
         let result = rx.try_recv();
         if result.is_err() {
-            if !app_context
-                .lock()
-                .await
+            // resolver 和 vhost 都结束之后才退出
+            let guard = app_context.lock().await;
+            if guard
                 .resolver_status
-                .contains(&crate::context::EngineStatus::Running)
+                .iter()
+                .all(|it| *it == EngineStatus::Stop)
+                && guard.vhost_status == EngineStatus::Stop
             {
                 break;
             }
+            drop(guard);
 
             tokio::time::sleep(Duration::from_secs(1)).await;
             continue;
         }
 
-        // 把结果格式化成行，临时先简单的格式化即可
+        // 按输出格式写入结果
         let result = result.unwrap();
         // println!("Found: {:?}", result);
-        let line = format!(
-            "{} - {:?} - {:?} - {:?} - {:?}\n",
-            result.domain,
-            result.ip,
-            result.cname,
-            result.code.unwrap_or(0),
-            result.title.unwrap_or_default(),
-        );
-        if let Err(e) = output_file.write(line.as_bytes()).await {
-            eprintln!("write file error, value: {:?}, error: {:?}", line, e)
-        };
+        if let Err(e) = writer.write(&result, &app_args).await {
+            eprintln!("{}", e);
+        }
+        if let Some(stdout) = stdout.as_mut() {
+            if let Err(e) = stdout.write(&result, &app_args).await {
+                eprintln!("{}", e);
+            }
+        }
+        if let Some(differ) = differ.as_mut() {
+            differ.record(&result);
+        }
+    }
+
+    if let Err(e) = writer.finish(&app_args).await {
+        eprintln!("{}", e);
+    }
+    if let Some(stdout) = stdout.as_mut() {
+        if let Err(e) = stdout.finish(&app_args).await {
+            eprintln!("{}", e);
+        }
+    }
+
+    // 输出变化报告
+    if let Some(differ) = differ {
+        let output = &app_args.diff_output_path;
+        if let Err(e) = tokio::fs::write(output, differ.report()).await {
+            eprintln!("write diff report {} error: {:?}", output, e);
+        }
     }
 
-    println!("saver engine finished.");
+    info!("saver engine finished.");
 }
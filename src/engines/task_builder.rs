@@ -3,24 +3,31 @@ use std::{collections::HashMap, process::exit, sync::Arc};
 use async_channel::Sender;
 use async_trait::async_trait;
 use itertools::Itertools;
+use log::info;
 use tokio::{fs::read_to_string, sync::Mutex};
 
 use crate::{
     args::AppArgs,
     context::{AppContext, EngineStatus},
+    scope::Scope,
 };
 
 #[async_trait]
 trait TaskBuilderTrait {
-    async fn build(task_channel: Sender<String>, app_args: &Arc<AppArgs>);
+    async fn build(task_channel: Sender<String>, app_args: &Arc<AppArgs>, scope: &Arc<Scope>);
+}
+
+/// 检查任务是否在扫描范围内，不在范围内的直接丢弃
+fn in_scope(task: &str, app_args: &Arc<AppArgs>, scope: &Arc<Scope>) -> bool {
+    scope.contains_domain(&format!("{}.{}", task, app_args.target))
 }
 
 /// 通过枚举进行构建任务
 struct EnumBuilder {}
 #[async_trait]
 impl TaskBuilderTrait for EnumBuilder {
-    async fn build(task_channel: Sender<String>, app_args: &Arc<AppArgs>) {
-        println!("TaskBuilder EnumBuilder start!");
+    async fn build(task_channel: Sender<String>, app_args: &Arc<AppArgs>, scope: &Arc<Scope>) {
+        info!("TaskBuilder EnumBuilder start!");
         // 字符池
         let mut pool = ('a'..='z').chain('0'..='9').collect::<Vec<_>>();
         pool.push('-');
@@ -28,11 +35,11 @@ impl TaskBuilderTrait for EnumBuilder {
         let length = app_args.length;
         let range = length.0..=length.1;
         for idx in range {
-            println!("start build lenght {idx} word.");
+            info!("start build lenght {idx} word.");
             let product = (1..=idx).map(|_| pool.iter()).multi_cartesian_product();
             for it in product {
                 let task = it.iter().cloned().join("");
-                if task.starts_with('-') {
+                if task.starts_with('-') || !in_scope(&task, app_args, scope) {
                     continue;
                 }
 
@@ -42,7 +49,7 @@ impl TaskBuilderTrait for EnumBuilder {
             }
         }
 
-        println!("TaskBuilder EnumBuilder finished!");
+        info!("TaskBuilder EnumBuilder finished!");
     }
 }
 
@@ -54,7 +61,7 @@ impl DictBuilder {
         let dict_path = dict_path.unwrap();
         if dict_path.is_empty() {
             // 使用内置字典
-            println!("No dict specified, use default dict.");
+            info!("No dict specified, use default dict.");
             Ok(include_str!("../../dicts/default.txt").to_owned())
         } else {
             // 读取用户提供的字典文件
@@ -104,8 +111,8 @@ impl DictBuilder {
 
 #[async_trait]
 impl TaskBuilderTrait for DictBuilder {
-    async fn build(task_channel: Sender<String>, app_args: &Arc<AppArgs>) {
-        println!("TaskBuilder DictBuilder start!");
+    async fn build(task_channel: Sender<String>, app_args: &Arc<AppArgs>, scope: &Arc<Scope>) {
+        info!("TaskBuilder DictBuilder start!");
 
         // 读取字典内容
         let dict_path = app_args.dict_path.as_ref();
@@ -171,6 +178,9 @@ impl TaskBuilderTrait for DictBuilder {
             }
 
             for task in tasks {
+                if !in_scope(&task, app_args, scope) {
+                    continue;
+                }
                 if let Err(e) = task_channel.send(task.clone()).await {
                     eprintln!(
                         "Error put task to channel, line: {}, task: {}, error: {:?}",
@@ -180,7 +190,7 @@ impl TaskBuilderTrait for DictBuilder {
             }
         }
 
-        println!("TaskBuilder DictBuilder finished!");
+        info!("TaskBuilder DictBuilder finished!");
     }
 }
 
@@ -189,16 +199,26 @@ pub async fn task_builder(
     task_channel: Sender<String>,
     app_args: Arc<AppArgs>,
     app_context: Arc<Mutex<AppContext>>,
+    scope: Arc<Scope>,
 ) {
     let mut guard = app_context.lock().await;
     guard.task_builder_status = EngineStatus::Running;
     drop(guard);
 
+    build_tasks(task_channel, &app_args, &scope).await;
+
+    app_context.lock().await.task_builder_status = EngineStatus::Stop;
+}
+
+/// 按照参数使用字典或者枚举生成任务，vhost 探测也用这里生成候选域名
+pub async fn build_tasks(
+    task_channel: Sender<String>,
+    app_args: &Arc<AppArgs>,
+    scope: &Arc<Scope>,
+) {
     if app_args.dict_path.is_some() {
-        DictBuilder::build(task_channel, &app_args).await;
+        DictBuilder::build(task_channel, app_args, scope).await;
     } else {
-        EnumBuilder::build(task_channel, &app_args).await;
+        EnumBuilder::build(task_channel, app_args, scope).await;
     }
-
-    app_context.lock().await.task_builder_status = EngineStatus::Stop;
 }
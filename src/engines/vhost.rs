@@ -0,0 +1,236 @@
+use std::{net::IpAddr, sync::Arc};
+
+use async_channel::{Receiver, Sender};
+use log::info;
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Client;
+use tokio::sync::Mutex;
+
+use super::task_builder::build_tasks;
+use crate::{
+    args::AppArgs,
+    context::{AppContext, EngineStatus, HttpProbe, ResolveResult, VHOST_SOURCE},
+    http::{build_http_client, build_url, is_internal_ip, vhost_probe},
+    scope::Scope,
+};
+
+/// 获取基准响应的任务，index 用于恢复原来的顺序
+struct BaselineTask {
+    index: usize,
+    ip: String,
+    url: String,
+    client: Client,
+}
+
+/// 一个 IP 上可以访问的 HTTP 服务，以及不存在的 Host 得到的基准响应
+struct Endpoint {
+    ip: String,
+    url: String,
+    client: Client,
+    baseline: HttpProbe,
+    // 两次基准响应的长度和 hash 是否一致，不一致说明页面是动态的，不能用来比较
+    stable_length: bool,
+    stable_hash: bool,
+}
+
+impl Endpoint {
+    /// 响应和基准响应的状态码、长度或者 hash 不同，说明这个 Host 对应了单独的站点
+    fn is_different(&self, probe: &HttpProbe) -> bool {
+        self.baseline.code != probe.code
+            || (self.stable_length && self.baseline.content_length != probe.content_length)
+            || (self.stable_hash && self.baseline.body_hash != probe.body_hash)
+    }
+}
+
+/// vhost engine
+/// 在解析结束后运行，对解析出的 IP 使用字典中的候选域名（包括解析失败的）作为 Host 发起请求
+pub async fn vhost(
+    result_channel: Sender<ResolveResult>,
+    app_args: Arc<AppArgs>,
+    app_context: Arc<Mutex<AppContext>>,
+    scope: Arc<Scope>,
+) {
+    info!("vhost engine start.");
+    let ips = app_context.lock().await.vhost_ips.clone();
+
+    // 构建 http client，和 resolver 一样内网地址可以不走代理
+    let http_client = build_http_client(&app_args, true).expect("Build HTTP Client Error!");
+    let direct_client = build_http_client(&app_args, false).expect("Build HTTP Client Error!");
+
+    // 获取每个 IP 上每个端口的基准响应，请求失败的端口不再探测
+    // 和候选域名的探测一样，由 task_count 个 worker 并发请求
+    let (baseline_tx, baseline_rx) = async_channel::unbounded::<BaselineTask>();
+    let mut index = 0;
+    for ip in ips {
+        let client = if app_args.proxy_bypass_internal && is_internal_ip(&ip) {
+            direct_client.clone()
+        } else {
+            http_client.clone()
+        };
+        let host = match ip.parse::<IpAddr>() {
+            Ok(IpAddr::V6(_)) => format!("[{}]", ip),
+            _ => ip.to_owned(),
+        };
+        for &port in app_args.ports.iter() {
+            for scheme in ["http", "https"] {
+                let _ = baseline_tx
+                    .send(BaselineTask {
+                        index,
+                        ip: ip.to_owned(),
+                        url: build_url(scheme, &host, port),
+                        client: client.clone(),
+                    })
+                    .await;
+                index += 1;
+            }
+        }
+    }
+    baseline_tx.close();
+
+    let mut workers = vec![];
+    for _ in 0..app_args.task_count {
+        workers.push(tokio::spawn(baseline_worker(
+            baseline_rx.clone(),
+            app_args.clone(),
+        )));
+    }
+    let mut endpoints = vec![];
+    for h in workers {
+        if let Ok(found) = h.await {
+            endpoints.extend(found);
+        }
+    }
+    // 保持 IP、端口、协议的顺序
+    endpoints.sort_by_key(|(index, _)| *index);
+    let endpoints = endpoints
+        .into_iter()
+        .map(|(_, endpoint)| endpoint)
+        .collect::<Vec<_>>();
+    info!("vhost engine found {} endpoints.", endpoints.len());
+
+    if !endpoints.is_empty() {
+        // 候选域名和 task_builder 使用同样的字典或者枚举规则生成
+        let (candidate_tx, candidate_rx) = async_channel::bounded::<String>(10240);
+        let builder = tokio::spawn({
+            let app_args = app_args.clone();
+            async move { build_tasks(candidate_tx, &app_args, &scope).await }
+        });
+
+        let endpoints = Arc::new(endpoints);
+        let mut workers = vec![];
+        for _ in 0..app_args.task_count {
+            workers.push(tokio::spawn(vhost_worker(
+                candidate_rx.clone(),
+                result_channel.clone(),
+                endpoints.clone(),
+                app_args.clone(),
+                app_context.clone(),
+            )));
+        }
+
+        let _ = builder.await;
+        for h in workers {
+            let _ = h.await;
+        }
+    }
+
+    app_context.lock().await.vhost_status = EngineStatus::Stop;
+    info!("vhost engine finished.");
+}
+
+/// 获取基准响应，同一个 URL 请求两次，判断页面是不是动态的
+async fn baseline_worker(
+    task_channel: Receiver<BaselineTask>,
+    app_args: Arc<AppArgs>,
+) -> Vec<(usize, Endpoint)> {
+    let mut endpoints = vec![];
+    while let Ok(task) = task_channel.recv().await {
+        let baseline = vhost_probe(
+            &task.url,
+            &random_host(&app_args.target),
+            &task.client,
+            &app_args,
+        )
+        .await;
+        if baseline.error.is_some() {
+            continue;
+        }
+        let second = vhost_probe(
+            &task.url,
+            &random_host(&app_args.target),
+            &task.client,
+            &app_args,
+        )
+        .await;
+        endpoints.push((
+            task.index,
+            Endpoint {
+                ip: task.ip,
+                url: task.url,
+                client: task.client,
+                stable_length: baseline.content_length == second.content_length,
+                stable_hash: baseline.body_hash == second.body_hash,
+                baseline,
+            },
+        ));
+    }
+    endpoints
+}
+
+async fn vhost_worker(
+    candidate_channel: Receiver<String>,
+    result_channel: Sender<ResolveResult>,
+    endpoints: Arc<Vec<Endpoint>>,
+    app_args: Arc<AppArgs>,
+    app_context: Arc<Mutex<AppContext>>,
+) {
+    // 候选域名全部生成并且处理完之后通道关闭
+    while let Ok(task) = candidate_channel.recv().await {
+        let domain = format!("{}.{}", task, app_args.target);
+        // DNS 已经解析出来的 IP 已经用这个域名探测过了，只探测其他 IP
+        let resolved_ips = app_context
+            .lock()
+            .await
+            .resolved_ips
+            .get(&domain)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut res = ResolveResult {
+            domain,
+            source: Some(VHOST_SOURCE.to_owned()),
+            ..Default::default()
+        };
+        for endpoint in endpoints.iter().filter(|it| !resolved_ips.contains(&it.ip)) {
+            let probe = vhost_probe(&endpoint.url, &res.domain, &endpoint.client, &app_args).await;
+            if probe.error.is_some() || !endpoint.is_different(&probe) {
+                continue;
+            }
+            if !res.ip.contains(&endpoint.ip) {
+                res.ip.push(endpoint.ip.to_owned());
+            }
+            if probe.url.starts_with("https://") {
+                res.https.push(probe);
+            } else {
+                res.http.push(probe);
+            }
+        }
+
+        if !res.ip.is_empty() {
+            info!("Found vhost: {} on {:?}", res.domain, res.ip);
+            if let Err(e) = result_channel.send(res).await {
+                eprintln!("Error put task to result_channel, error: {:?}", e);
+            }
+        }
+    }
+}
+
+/// 随机生成一个不存在的 Host，用于获取基准响应
+fn random_host(target: &str) -> String {
+    let label: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    format!("{}.{}", label.to_lowercase(), target)
+}
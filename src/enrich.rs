@@ -0,0 +1,58 @@
+mod geoip;
+mod provider;
+mod technology;
+
+use std::net::IpAddr;
+
+use reqwest::header::HeaderMap;
+
+use crate::{args::AppArgs, context::ResolveResult};
+
+pub use geoip::GeoIp;
+pub use provider::{ProviderKind, ProviderMatcher};
+pub use technology::TechMatcher;
+
+/// 对解析结果做离线的补充信息
+pub struct Enricher {
+    providers: ProviderMatcher,
+    geoip: GeoIp,
+    technologies: TechMatcher,
+}
+
+impl Enricher {
+    /// 按照参数加载各类数据
+    pub async fn load(app_args: &AppArgs) -> Result<Self, String> {
+        let providers = ProviderMatcher::load(app_args.provider_data_path.as_ref()).await?;
+        let geoip = GeoIp::load(app_args.asn_db_path.as_ref(), app_args.geo_db_path.as_ref());
+        let technologies = TechMatcher::load(app_args.tech_rules_path.as_ref()).await?;
+        Ok(Self {
+            providers,
+            geoip,
+            technologies,
+        })
+    }
+
+    /// 识别 HTTP 响应的技术栈
+    pub fn match_technologies(&self, headers: &HeaderMap, body: &str) -> Vec<String> {
+        self.technologies.match_response(headers, body)
+    }
+
+    pub fn enrich(&self, result: &mut ResolveResult) {
+        if let Some((name, kind)) = self.providers.classify(result) {
+            result.provider = Some(name.to_owned());
+            result.cdn = kind == ProviderKind::Cdn;
+        }
+
+        if self.geoip.is_enabled() {
+            for ip in result.ip.iter() {
+                if let Some(info) = ip
+                    .parse::<IpAddr>()
+                    .ok()
+                    .and_then(|it| self.geoip.lookup(it))
+                {
+                    result.ip_info.insert(ip.to_owned(), info);
+                }
+            }
+        }
+    }
+}
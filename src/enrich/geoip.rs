@@ -0,0 +1,66 @@
+use std::net::IpAddr;
+
+use maxminddb::{geoip2, Reader};
+
+use crate::context::IpInfo;
+
+/// 基于本地 MaxMind 格式数据库（GeoLite2 ASN / City / Country 或兼容格式）的 IP 信息查询
+/// 数据库不存在或者打开失败时只关闭对应的查询，不影响扫描
+pub struct GeoIp {
+    asn_reader: Option<Reader<Vec<u8>>>,
+    country_reader: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIp {
+    pub fn load(asn_path: Option<&String>, country_path: Option<&String>) -> Self {
+        Self {
+            asn_reader: asn_path.and_then(Self::open),
+            country_reader: country_path.and_then(Self::open),
+        }
+    }
+
+    fn open(path: &String) -> Option<Reader<Vec<u8>>> {
+        match Reader::open_readfile(path) {
+            Ok(reader) => Some(reader),
+            Err(e) => {
+                eprintln!(
+                    "Open mmdb failed, path: {}, error: {:?}, GeoIP enrichment disabled.",
+                    path, e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.asn_reader.is_some() || self.country_reader.is_some()
+    }
+
+    /// 查询 IP 的 ASN、组织和国家，都没有查到时返回 None
+    pub fn lookup(&self, ip: IpAddr) -> Option<IpInfo> {
+        let mut info = IpInfo::default();
+
+        if let Some(reader) = &self.asn_reader {
+            if let Ok(asn) = reader.lookup::<geoip2::Asn>(ip) {
+                info.asn = asn.autonomous_system_number;
+                info.org = asn.autonomous_system_organization.map(|it| it.to_owned());
+            }
+        }
+
+        // City 数据库是 Country 数据库的超集，这里只取国家信息
+        if let Some(reader) = &self.country_reader {
+            if let Ok(country) = reader.lookup::<geoip2::Country>(ip) {
+                info.country = country
+                    .country
+                    .and_then(|it| it.iso_code)
+                    .map(|it| it.to_owned());
+            }
+        }
+
+        if info.asn.is_none() && info.org.is_none() && info.country.is_none() {
+            None
+        } else {
+            Some(info)
+        }
+    }
+}
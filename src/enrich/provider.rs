@@ -0,0 +1,152 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use tokio::fs::read_to_string;
+
+use crate::context::ResolveResult;
+
+/// 厂商类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProviderKind {
+    Cdn,
+    Cloud,
+}
+
+#[derive(Debug)]
+struct Provider {
+    name: String,
+    kind: ProviderKind,
+}
+
+/// CDN / 云厂商识别，数据格式见 data/providers.txt
+pub struct ProviderMatcher {
+    providers: Vec<Provider>,
+    // (CIDR, providers 的下标)
+    cidrs: Vec<(IpNet, usize)>,
+    // (CNAME 后缀, providers 的下标)
+    suffixes: Vec<(String, usize)>,
+}
+
+impl ProviderMatcher {
+    /// 根据参数决定使用内置数据还是从文件读取数据
+    pub async fn load(path: Option<&String>) -> Result<Self, String> {
+        match path {
+            Some(path) => {
+                let content = read_to_string(path).await.map_err(|e| {
+                    format!("Read provider data failed, path: {}, error: {:?}", path, e)
+                })?;
+                Self::parse(&content)
+            }
+            None => Self::parse(include_str!("../../data/providers.txt")),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut matcher = Self {
+            providers: vec![],
+            cidrs: vec![],
+            suffixes: vec![],
+        };
+
+        for (lineno, line) in content.lines().enumerate() {
+            // skip empty line and comment line
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            if parts.len() != 4 {
+                return Err(format!(
+                    "Invalid provider data at line {}: {}",
+                    lineno + 1,
+                    line
+                ));
+            }
+            let kind = match parts[1] {
+                "cdn" => ProviderKind::Cdn,
+                "cloud" => ProviderKind::Cloud,
+                _ => {
+                    return Err(format!(
+                        "Invalid provider type at line {}: {}",
+                        lineno + 1,
+                        line
+                    ))
+                }
+            };
+
+            let index = match matcher
+                .providers
+                .iter()
+                .position(|it| it.name == parts[0] && it.kind == kind)
+            {
+                Some(index) => index,
+                None => {
+                    matcher.providers.push(Provider {
+                        name: parts[0].to_owned(),
+                        kind,
+                    });
+                    matcher.providers.len() - 1
+                }
+            };
+
+            match parts[2] {
+                "cidr" => match parts[3].parse::<IpNet>() {
+                    Ok(cidr) => matcher.cidrs.push((cidr, index)),
+                    Err(_) => return Err(format!("Invalid CIDR at line {}: {}", lineno + 1, line)),
+                },
+                "cname" => matcher
+                    .suffixes
+                    .push((parts[3].trim_end_matches('.').to_lowercase(), index)),
+                _ => {
+                    return Err(format!(
+                        "Invalid match type at line {}: {}",
+                        lineno + 1,
+                        line
+                    ))
+                }
+            }
+        }
+
+        Ok(matcher)
+    }
+
+    /// 识别解析结果所属的厂商，CNAME 比 IP 段更准确，优先匹配 CNAME
+    pub fn classify(&self, result: &ResolveResult) -> Option<(&str, ProviderKind)> {
+        let cnames = result.cname.iter().chain(
+            result
+                .records
+                .iter()
+                .filter(|it| it.record_type == "CNAME")
+                .map(|it| &it.value),
+        );
+        for cname in cnames {
+            if let Some(provider) = self.match_cname(cname) {
+                return Some((&provider.name, provider.kind));
+            }
+        }
+
+        for ip in result.ip.iter().filter_map(|it| it.parse::<IpAddr>().ok()) {
+            if let Some(provider) = self.match_ip(&ip) {
+                return Some((&provider.name, provider.kind));
+            }
+        }
+
+        None
+    }
+
+    fn match_cname(&self, cname: &str) -> Option<&Provider> {
+        let cname = cname.trim_end_matches('.').to_lowercase();
+        self.suffixes
+            .iter()
+            .find(|(suffix, _)| cname.ends_with(suffix.as_str()))
+            .map(|(_, index)| &self.providers[*index])
+    }
+
+    fn match_ip(&self, ip: &IpAddr) -> Option<&Provider> {
+        self.cidrs
+            .iter()
+            .find(|(cidr, _)| cidr.contains(ip))
+            .map(|(_, index)| &self.providers[*index])
+    }
+}
@@ -0,0 +1,116 @@
+use regex::Regex;
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use tokio::fs::read_to_string;
+
+/// 指纹匹配的位置
+enum Location {
+    Header(String),
+    Body,
+    Cookie,
+}
+
+struct TechRule {
+    name: String,
+    location: Location,
+    regex: Regex,
+}
+
+/// 技术栈指纹识别，数据格式见 data/technologies.txt
+pub struct TechMatcher {
+    rules: Vec<TechRule>,
+}
+
+impl TechMatcher {
+    /// 根据参数决定使用内置规则还是从文件读取规则
+    pub async fn load(path: Option<&String>) -> Result<Self, String> {
+        match path {
+            Some(path) => {
+                let content = read_to_string(path).await.map_err(|e| {
+                    format!("Read tech rules failed, path: {}, error: {:?}", path, e)
+                })?;
+                Self::parse(&content)
+            }
+            None => Self::parse(include_str!("../../data/technologies.txt")),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut rules = vec![];
+
+        for (lineno, line) in content.lines().enumerate() {
+            // skip empty line and comment line
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // 正则中可能有空格，只拆前两个字段
+            let parts = line.splitn(3, char::is_whitespace).collect::<Vec<_>>();
+            if parts.len() != 3 {
+                return Err(format!(
+                    "Invalid tech rule at line {}: {}",
+                    lineno + 1,
+                    line
+                ));
+            }
+            let location = match parts[1] {
+                "body" => Location::Body,
+                "cookie" => Location::Cookie,
+                _ => match parts[1].strip_prefix("header:") {
+                    Some(header) => Location::Header(header.to_lowercase()),
+                    None => {
+                        return Err(format!(
+                            "Invalid tech rule location at line {}: {}",
+                            lineno + 1,
+                            line
+                        ))
+                    }
+                },
+            };
+            let regex = Regex::new(parts[2].trim())
+                .map_err(|e| format!("Invalid tech rule regex at line {}: {:?}", lineno + 1, e))?;
+
+            rules.push(TechRule {
+                name: parts[0].to_owned(),
+                location,
+                regex,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// 对一个响应匹配全部规则，返回去重后的技术栈名称
+    pub fn match_response(&self, headers: &HeaderMap, body: &str) -> Vec<String> {
+        let cookies = Self::join_header(headers, SET_COOKIE.as_str());
+
+        let mut result: Vec<String> = vec![];
+        for rule in self.rules.iter() {
+            if result.contains(&rule.name) {
+                continue;
+            }
+            let matched = match &rule.location {
+                Location::Header(name) => {
+                    headers.contains_key(name.as_str())
+                        && rule.regex.is_match(&Self::join_header(headers, name))
+                }
+                Location::Body => rule.regex.is_match(body),
+                Location::Cookie => rule.regex.is_match(&cookies),
+            };
+            if matched {
+                result.push(rule.name.to_owned());
+            }
+        }
+        result
+    }
+
+    /// 同名的响应头可能有多个，拼在一起匹配
+    fn join_header(headers: &HeaderMap, name: &str) -> String {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|it| it.to_str().ok())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,471 @@
+mod cert;
+mod fingerprint;
+mod harvest;
+mod title;
+
+use std::{collections::BTreeMap, error::Error, net::IpAddr, time::Duration};
+
+use rand::seq::SliceRandom;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_LENGTH,
+        CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY, CONTENT_TYPE, COOKIE, HOST,
+        LOCATION, SERVER, USER_AGENT,
+    },
+    redirect::Policy,
+    tls::TlsInfo,
+    Client, ClientBuilder, Method, Proxy, RequestBuilder, Response, StatusCode, Url,
+};
+
+use crate::{
+    args::AppArgs,
+    context::{HttpProbe, RedirectHop},
+    enrich::Enricher,
+    scope::Scope,
+};
+
+use cert::parse_certificate;
+use fingerprint::{body_hash, favicon_hash};
+use harvest::script_sources;
+pub use harvest::Harvester;
+use title::extract_title;
+
+/// 默认端口和对应的协议，http://host 和 https://host 各探测一次
+const DEFAULT_PORTS: [(u16, &str); 2] = [(80, "http"), (443, "https")];
+
+/// 常见的 HTTPS 端口，这些端口优先尝试 https
+const HTTPS_PORTS: [u16; 4] = [4443, 8443, 9443, 10443];
+
+/// 最多跟随的跳转次数
+const MAX_REDIRECTS: usize = 10;
+
+/// 开启 crawl 时每个页面最多请求的 JS 数量
+const MAX_SCRIPTS: usize = 10;
+
+/// 构建 http client，use_proxy 为 false 时即使配置了代理也直接连接
+/// 跳转由 http_worker 手动处理，以便记录跳转链
+pub fn build_http_client(app_args: &AppArgs, use_proxy: bool) -> Result<Client, reqwest::Error> {
+    // 自定义请求头和 Cookie，参数解析时已经校验过格式
+    let mut headers = HeaderMap::new();
+    for (name, value) in app_args.headers.iter() {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    if let Some(Ok(cookie)) = app_args.cookie.as_ref().map(|it| HeaderValue::from_str(it)) {
+        headers.insert(COOKIE, cookie);
+    }
+
+    let mut builder = ClientBuilder::new()
+        .connect_timeout(Duration::from_secs(app_args.connect_timeout))
+        .default_headers(headers)
+        .redirect(Policy::none())
+        .tls_info(true)
+        .danger_accept_invalid_certs(true);
+    if let (Some(proxy), true) = (&app_args.proxy, use_proxy) {
+        builder = builder.proxy(Proxy::all(proxy.as_str())?);
+    }
+    builder.build()
+}
+
+/// 是否是内网、回环或者链路本地地址
+pub fn is_internal_ip(ip: &str) -> bool {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        // fc00::/7 为 IPv6 的内网地址，fe80::/10 为链路本地地址
+        Ok(IpAddr::V6(ip)) => {
+            ip.is_loopback()
+                || (ip.segments()[0] & 0xfe00) == 0xfc00
+                || (ip.segments()[0] & 0xffc0) == 0xfe80
+        }
+        Err(_) => false,
+    }
+}
+
+/// 按端口获取 HTTP 状态码和网页 title
+/// 80 端口探测 http，443 端口探测 https，两个结果分开记录，失败的记录失败原因
+/// 其他端口先尝试更可能的协议，失败后再尝试另一个，只记录有响应的那个，都失败的端口不记录
+pub async fn http_worker(
+    target: &str,
+    http_client: &Client,
+    app_args: &AppArgs,
+    enricher: &Enricher,
+    harvester: &Harvester,
+    scope: &Scope,
+) -> (Vec<HttpProbe>, Vec<HttpProbe>) {
+    let mut http_probes = vec![];
+    let mut https_probes = vec![];
+    for &port in app_args.ports.iter() {
+        if let Some(&(_, scheme)) = DEFAULT_PORTS.iter().find(|(it, _)| *it == port) {
+            let url = build_url(scheme, target, port);
+            let result = probe(&url, http_client, app_args, enricher, harvester, scope).await;
+            push_probe(scheme, result, &mut http_probes, &mut https_probes);
+            continue;
+        }
+
+        let schemes = if HTTPS_PORTS.contains(&port) {
+            ["https", "http"]
+        } else {
+            ["http", "https"]
+        };
+        for scheme in schemes {
+            let url = build_url(scheme, target, port);
+            let result = probe(&url, http_client, app_args, enricher, harvester, scope).await;
+            if result.code.is_some() {
+                push_probe(scheme, result, &mut http_probes, &mut https_probes);
+                break;
+            }
+        }
+    }
+
+    (http_probes, https_probes)
+}
+
+fn push_probe(
+    scheme: &str,
+    probe: HttpProbe,
+    http_probes: &mut Vec<HttpProbe>,
+    https_probes: &mut Vec<HttpProbe>,
+) {
+    match scheme {
+        "https" => https_probes.push(probe),
+        _ => http_probes.push(probe),
+    }
+}
+
+/// 手动跟随跳转，记录每一跳的状态码和 Location
+/// 跳转到目标主域或者扫描范围之外时不再继续请求，只把跳转地址记到 offsite_redirect 里
+/// 同时从 Location、CSP、CORS 响应头和页面内容中收集目标主域下的域名
+async fn probe(
+    url: &str,
+    http_client: &Client,
+    app_args: &AppArgs,
+    enricher: &Enricher,
+    harvester: &Harvester,
+    scope: &Scope,
+) -> HttpProbe {
+    let mut probe = HttpProbe {
+        url: url.to_owned(),
+        final_url: url.to_owned(),
+        ..Default::default()
+    };
+
+    // 开启 head-first 时先发 HEAD，页面正常时再用 GET 获取内容
+    let mut method = if app_args.head_first {
+        Method::HEAD
+    } else {
+        Method::GET
+    };
+    let mut html = String::new();
+
+    loop {
+        let request = http_client.request(method.clone(), &probe.final_url);
+        let resp = match send(request, app_args).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                probe.error = Some(e);
+                break;
+            }
+        };
+        let status = resp.status();
+        let code = status.as_u16();
+        probe.code = Some(code);
+
+        // CSP 和 CORS 响应头中经常会列出其他子域名
+        for name in [CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY] {
+            for value in resp.headers().get_all(name).iter() {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                harvester.scan(&value, "csp", &mut probe.harvested);
+            }
+        }
+        if let Some(origin) = header_value(resp.headers(), ACCESS_CONTROL_ALLOW_ORIGIN.as_str()) {
+            harvester.scan(origin, "cors", &mut probe.harvested);
+        }
+
+        // 只记录第一次请求的证书，也就是目标域名自己的证书
+        if probe.certificate.is_none() && probe.redirects.is_empty() {
+            probe.certificate = resp
+                .extensions()
+                .get::<TlsInfo>()
+                .and_then(|it| it.peer_certificate())
+                .and_then(parse_certificate);
+        }
+
+        // 服务器不支持 HEAD 时换成 GET 重新请求
+        if method == Method::HEAD
+            && (status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::NOT_IMPLEMENTED)
+        {
+            method = Method::GET;
+            continue;
+        }
+
+        // 解析 Location，相对地址需要基于当前 URL 拼接
+        let location = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|it| it.to_str().ok())
+            .and_then(|it| resp.url().join(it).ok());
+        match location {
+            Some(location) if status.is_redirection() => {
+                harvester.scan(location.as_str(), "location", &mut probe.harvested);
+                probe.redirects.push(RedirectHop {
+                    code,
+                    location: location.to_string(),
+                });
+                if !in_scope(&location, &app_args.target, scope)
+                    || resolves_to_blocked(&location, scope).await
+                {
+                    probe.offsite_redirect = Some(location.to_string());
+                    break;
+                }
+                if probe.redirects.len() >= MAX_REDIRECTS {
+                    probe.error = Some("too many redirects".to_owned());
+                    break;
+                }
+                probe.final_url = location.to_string();
+            }
+            _ if method == Method::HEAD && status.is_success() => {
+                method = Method::GET;
+            }
+            _ => {
+                // HEAD 请求没有响应体，只记录响应头中的信息
+                let headers = resp.headers().clone();
+                let body = if method == Method::GET {
+                    read_body(resp, app_args).await
+                } else {
+                    vec![]
+                };
+                probe.server = header_value(&headers, SERVER.as_str()).map(|it| it.to_owned());
+                probe.powered_by = header_value(&headers, "x-powered-by").map(|it| it.to_owned());
+                probe.content_length = header_value(&headers, CONTENT_LENGTH.as_str())
+                    .and_then(|it| it.parse::<u64>().ok());
+                html = String::from_utf8_lossy(&body).into_owned();
+                probe.technologies = enricher.match_technologies(&headers, &html);
+                harvester.scan(&html, "http-body", &mut probe.harvested);
+                if method == Method::GET {
+                    probe.title =
+                        extract_title(&body, header_value(&headers, CONTENT_TYPE.as_str()));
+                    probe.content_length = probe.content_length.or(Some(body.len() as u64));
+                    probe.body_hash = Some(body_hash(&body));
+                }
+                break;
+            }
+        }
+    }
+
+    // 拿到了页面才去取 favicon
+    if probe.body_hash.is_some() {
+        probe.favicon_hash = fetch_favicon_hash(&probe.final_url, http_client, app_args).await;
+        if app_args.crawl {
+            crawl(
+                &probe.final_url,
+                &html,
+                http_client,
+                app_args,
+                harvester,
+                scope,
+                &mut probe.harvested,
+            )
+            .await;
+        }
+    }
+
+    probe
+}
+
+/// 请求 robots.txt、sitemap.xml 和页面引用的扫描范围内的 JS，从中收集域名
+async fn crawl(
+    url: &str,
+    html: &str,
+    http_client: &Client,
+    app_args: &AppArgs,
+    harvester: &Harvester,
+    scope: &Scope,
+    found: &mut BTreeMap<String, String>,
+) {
+    let Ok(base) = Url::parse(url) else {
+        return;
+    };
+
+    let mut targets = vec![];
+    for (path, source) in [("/robots.txt", "robots"), ("/sitemap.xml", "sitemap")] {
+        if let Ok(url) = base.join(path) {
+            targets.push((url, source));
+        }
+    }
+    // 第三方的 JS 基本不会提到目标的域名，只请求目标主域下并且在扫描范围内的
+    let scripts = script_sources(html)
+        .iter()
+        .filter_map(|it| base.join(it).ok())
+        .filter(|it| in_scope(it, &app_args.target, scope))
+        .take(MAX_SCRIPTS)
+        .map(|it| (it, "javascript"))
+        .collect::<Vec<_>>();
+    targets.extend(scripts);
+
+    for (url, source) in targets {
+        if resolves_to_blocked(&url, scope).await {
+            continue;
+        }
+        let resp = match send(http_client.get(url), app_args).await {
+            Ok(resp) if resp.status().is_success() => resp,
+            _ => continue,
+        };
+        let body = read_body(resp, app_args).await;
+        harvester.scan(&String::from_utf8_lossy(&body), source, found);
+    }
+}
+
+/// 发送请求，配置了 User-Agent 列表时每次随机选一个
+/// 连接超时由 client 控制，这里再限制等待响应头的时间
+async fn send(request: RequestBuilder, app_args: &AppArgs) -> Result<Response, String> {
+    let request = match app_args.user_agents.choose(&mut rand::thread_rng()) {
+        Some(user_agent) => request.header(USER_AGENT, user_agent),
+        None => request,
+    };
+    let timeout = Duration::from_secs(app_args.connect_timeout + app_args.read_timeout);
+    match tokio::time::timeout(timeout, request.send()).await {
+        Ok(Ok(resp)) => Ok(resp),
+        Ok(Err(e)) => Err(error_reason(&e)),
+        Err(_) => Err("timeout".to_owned()),
+    }
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|it| it.to_str().ok())
+}
+
+/// 直接请求 IP 并指定 Host 请求头，用于虚拟主机探测
+/// 不跟随跳转，响应体长度使用实际读到的长度，方便和基准响应比较
+pub async fn vhost_probe(
+    url: &str,
+    host: &str,
+    http_client: &Client,
+    app_args: &AppArgs,
+) -> HttpProbe {
+    let mut probe = HttpProbe {
+        url: url.to_owned(),
+        final_url: url.to_owned(),
+        ..Default::default()
+    };
+
+    match send(http_client.get(url).header(HOST, host), app_args).await {
+        Ok(resp) => {
+            let code = resp.status().as_u16();
+            probe.code = Some(code);
+            if let Some(location) = header_value(resp.headers(), LOCATION.as_str()) {
+                probe.redirects.push(RedirectHop {
+                    code,
+                    location: location.to_owned(),
+                });
+            }
+            let headers = resp.headers().clone();
+            let body = read_body(resp, app_args).await;
+            probe.title = extract_title(&body, header_value(&headers, CONTENT_TYPE.as_str()));
+            probe.server = header_value(&headers, SERVER.as_str()).map(|it| it.to_owned());
+            probe.content_length = Some(body.len() as u64);
+            probe.body_hash = Some(body_hash(&body));
+        }
+        Err(e) => probe.error = Some(e),
+    }
+
+    probe
+}
+
+/// 请求站点根目录下的 /favicon.ico 并计算 hash
+async fn fetch_favicon_hash(url: &str, http_client: &Client, app_args: &AppArgs) -> Option<i32> {
+    let favicon_url = Url::parse(url).ok()?.join("/favicon.ico").ok()?;
+    let resp = send(http_client.get(favicon_url), app_args).await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let favicon = read_body(resp, app_args).await;
+    if favicon.is_empty() {
+        None
+    } else {
+        Some(favicon_hash(&favicon))
+    }
+}
+
+/// 读取响应体，超过 max_body_size 的部分丢弃，每次读取的等待时间不超过 read_timeout
+/// 读取过程中出错时保留已经读到的内容，部分服务器关闭连接时不发送 close_notify
+async fn read_body(mut resp: Response, app_args: &AppArgs) -> Vec<u8> {
+    let timeout = Duration::from_secs(app_args.read_timeout);
+    let mut body: Vec<u8> = vec![];
+    while let Ok(Ok(Some(chunk))) = tokio::time::timeout(timeout, resp.chunk()).await {
+        body.extend_from_slice(&chunk);
+        if body.len() >= app_args.max_body_size {
+            body.truncate(app_args.max_body_size);
+            break;
+        }
+    }
+    body
+}
+
+/// URL 的 host 是否是目标主域或者其子域名
+fn in_apex(url: &Url, apex: &str) -> bool {
+    match url.host_str() {
+        Some(host) => {
+            let host = host.trim_end_matches('.').to_lowercase();
+            let apex = apex.to_lowercase();
+            host == apex || host.ends_with(&format!(".{}", apex))
+        }
+        None => false,
+    }
+}
+
+/// URL 是否可以请求：host 在目标主域下，并且没有被扫描范围排除
+/// 跳转到范围外的地址和 IP 地址都不跟随，favicon 和 crawl 都基于最后请求的地址，也不会离开范围
+fn in_scope(url: &Url, apex: &str, scope: &Scope) -> bool {
+    in_apex(url, apex)
+        && url
+            .host_str()
+            .is_some_and(|host| scope.contains_domain(host.trim_end_matches('.')))
+}
+
+/// URL 的 host 是否解析到了黑名单中的 IP，和发起请求时一样使用系统 DNS 解析
+/// 跳转和 crawl 请求的 host 没有经过 resolver 的黑名单检查，请求之前需要检查
+async fn resolves_to_blocked(url: &Url, scope: &Scope) -> bool {
+    if !scope.has_blocklist() {
+        return false;
+    }
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(addrs) => addrs
+            .into_iter()
+            .any(|it| scope.is_blocked(&it.ip().to_string())),
+        // 解析失败时请求也会失败
+        Err(_) => false,
+    }
+}
+
+/// 把 reqwest 的错误转换成简短的失败原因
+fn error_reason(e: &reqwest::Error) -> String {
+    if e.is_timeout() {
+        return "timeout".to_owned();
+    }
+
+    // 取最底层的错误，例如 Connection refused、证书错误等
+    let mut source: &dyn Error = e;
+    while let Some(inner) = source.source() {
+        source = inner;
+    }
+    if e.is_connect() {
+        format!("connect error: {}", source)
+    } else {
+        source.to_string()
+    }
+}
+
+/// 构造 URL，默认端口不写到 URL 里
+pub fn build_url(scheme: &str, target: &str, port: u16) -> String {
+    match (scheme, port) {
+        ("http", 80) | ("https", 443) => format!("{}://{}", scheme, target),
+        _ => format!("{}://{}:{}", scheme, target, port),
+    }
+}
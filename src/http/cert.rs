@@ -0,0 +1,29 @@
+use x509_parser::{certificate::X509Certificate, extensions::GeneralName, prelude::FromDer};
+
+use crate::context::CertInfo;
+
+/// 解析叶子证书（DER 格式），提取主题、颁发者、SAN 和有效期
+pub fn parse_certificate(der: &[u8]) -> Option<CertInfo> {
+    let (_, cert) = X509Certificate::from_der(der).ok()?;
+
+    let mut sans = vec![];
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in san.value.general_names.iter() {
+            if let GeneralName::DNSName(name) = name {
+                sans.push(name.to_string());
+            }
+        }
+    }
+
+    let validity = cert.validity();
+    Some(CertInfo {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        sans,
+        not_before: validity.not_before.to_rfc2822().unwrap_or_default(),
+        not_after: validity.not_after.to_rfc2822().unwrap_or_default(),
+        expired: validity.time_to_expiration().is_none(),
+        // 主题和颁发者相同的视为自签名证书
+        self_signed: cert.subject().as_raw() == cert.issuer().as_raw(),
+    })
+}
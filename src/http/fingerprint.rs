@@ -0,0 +1,47 @@
+use std::io::Cursor;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+
+/// 响应体的 sha256
+pub fn body_hash(body: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(body))
+}
+
+/// 计算 favicon 的 mmh3 hash，算法和 Shodan 一致:
+/// mmh3.hash(base64.encodebytes(favicon))，base64 每 76 个字符换行，末尾也有换行
+pub fn favicon_hash(favicon: &[u8]) -> i32 {
+    let encoded = STANDARD.encode(favicon);
+    let mut content = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for (idx, c) in encoded.chars().enumerate() {
+        if idx > 0 && idx % 76 == 0 {
+            content.push('\n');
+        }
+        content.push(c);
+    }
+    content.push('\n');
+
+    murmur3::murmur3_32(&mut Cursor::new(content.as_bytes()), 0).unwrap_or_default() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favicon_hash_matches_shodan() {
+        // 期望值由 python 的 mmh3.hash(base64.encodebytes(data)) 计算
+        // 256 字节编码后超过 76 个字符，覆盖了中间换行的情况
+        let favicon = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(favicon_hash(&favicon), -757223386);
+        assert_eq!(favicon_hash(b"hello"), 1155597304);
+    }
+
+    #[test]
+    fn body_hash_is_sha256_hex() {
+        assert_eq!(
+            body_hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use scraper::{Html, Selector};
+
+/// 从响应头、响应体等文本中提取目标主域下的域名
+pub struct Harvester {
+    regex: Regex,
+}
+
+impl Harvester {
+    /// 标签只匹配 ASCII 字符，开启 Unicode 时 (?i) 下的 [a-z] 还会匹配 K (U+212A) 等多字节字符
+    pub fn new(apex: &str) -> Self {
+        let pattern = format!(
+            r"(?i)(?:(?-u:[a-z0-9](?:[a-z0-9-]{{0,61}}[a-z0-9])?)\.)+{}",
+            regex::escape(apex)
+        );
+        Self {
+            regex: Regex::new(&pattern).unwrap(),
+        }
+    }
+
+    /// 扫描文本，新域名和来源记到 found 里，已经有来源的域名不覆盖
+    pub fn scan(&self, text: &str, source: &str, found: &mut BTreeMap<String, String>) {
+        let bytes = text.as_bytes();
+        for m in self.regex.find_iter(text) {
+            // 后面还连着域名字符的，例如 example.com.evil.net，不是目标主域下的域名
+            let next = &bytes[m.end()..];
+            match next.first() {
+                Some(c) if c.is_ascii_alphanumeric() || *c == b'-' => continue,
+                Some(b'.') if next.get(1).is_some_and(|c| c.is_ascii_alphanumeric()) => continue,
+                _ => {}
+            }
+
+            // URL 编码中的 %2F、%3A 等会被当成域名的一部分，去掉
+            let mut domain = m.as_str();
+            let start = m.start();
+            let escaped = domain
+                .as_bytes()
+                .get(..2)
+                .is_some_and(|it| it.iter().all(u8::is_ascii_hexdigit));
+            if start > 0 && bytes[start - 1] == b'%' && escaped && domain.len() > 2 {
+                domain = &domain[2..];
+            }
+            let domain = domain.trim_start_matches(['-', '.']).to_lowercase();
+            found.entry(domain).or_insert_with(|| source.to_owned());
+        }
+    }
+}
+
+/// 页面中引用的 JS 地址，保持页面中的顺序
+pub fn script_sources(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("script[src]").unwrap();
+    document
+        .select(&selector)
+        .filter_map(|it| it.value().attr("src"))
+        .map(|it| it.trim().to_owned())
+        .filter(|it| !it.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(text: &str) -> Vec<String> {
+        let mut found = BTreeMap::new();
+        Harvester::new("example.com").scan(text, "test", &mut found);
+        found.into_keys().collect()
+    }
+
+    #[test]
+    fn finds_subdomains_in_text() {
+        assert_eq!(
+            scan(r#"<a href="https://API.Example.com/v1">x</a> mail@dev.example.com."#),
+            vec!["api.example.com", "dev.example.com"]
+        );
+    }
+
+    #[test]
+    fn skips_other_domains() {
+        assert!(scan("notexample.com www.example.com.evil.net www.example.company").is_empty());
+    }
+
+    #[test]
+    fn strips_url_encoding_prefix() {
+        assert_eq!(
+            scan("redirect=https%3A%2F%2Fsso.example.com%2Flogin"),
+            vec!["sso.example.com"]
+        );
+    }
+
+    #[test]
+    fn ignores_non_ascii_letters() {
+        // 开启 Unicode 时 (?i) 下的 [a-z] 会匹配 K (U+212A)，切片会落在字符中间
+        assert_eq!(scan("%\u{212A}a.example.com"), vec!["a.example.com"]);
+        assert_eq!(scan("%\u{212A}\u{212A}.example.com"), Vec::<String>::new());
+        assert_eq!(scan("%zzsso.example.com"), vec!["zzsso.example.com"]);
+    }
+
+    #[test]
+    fn keeps_first_source() {
+        let harvester = Harvester::new("example.com");
+        let mut found = BTreeMap::new();
+        harvester.scan("a.example.com", "csp", &mut found);
+        harvester.scan("a.example.com", "http-body", &mut found);
+        assert_eq!(
+            found.get("a.example.com").map(|it| it.as_str()),
+            Some("csp")
+        );
+    }
+
+    #[test]
+    fn script_sources_keep_order() {
+        let html = r#"<script src="/a.js"></script><script>x</script><script src=" https://cdn.example.com/b.js "></script>"#;
+        assert_eq!(
+            script_sources(html),
+            vec!["/a.js", "https://cdn.example.com/b.js"]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::OnceLock;
+
+use encoding_rs::{Encoding, UTF_8};
+use regex::bytes::Regex;
+use scraper::{Html, Selector};
+
+/// 选择器和正则只编译一次
+static TITLE_SELECTOR: OnceLock<Selector> = OnceLock::new();
+static OG_TITLE_SELECTOR: OnceLock<Selector> = OnceLock::new();
+static META_CHARSET_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// 从网页中提取 title
+/// 按照 Content-Type、<meta charset> 的顺序确定编码，都没有时按 UTF-8 解码
+/// <title> 为空时使用 og:title
+pub fn extract_title(body: &[u8], content_type: Option<&str>) -> Option<String> {
+    let encoding = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| charset_from_meta(body))
+        .unwrap_or(UTF_8);
+    let (html, _, _) = encoding.decode(body);
+    let document = Html::parse_document(&html);
+
+    let title_selector = TITLE_SELECTOR.get_or_init(|| Selector::parse("title").unwrap());
+    let title = document
+        .select(title_selector)
+        .next()
+        .map(|it| normalize(&it.text().collect::<String>()))
+        .filter(|it| !it.is_empty());
+    if title.is_some() {
+        return title;
+    }
+
+    let og_selector =
+        OG_TITLE_SELECTOR.get_or_init(|| Selector::parse(r#"meta[property="og:title"]"#).unwrap());
+    document
+        .select(og_selector)
+        .next()
+        .and_then(|it| it.value().attr("content"))
+        .map(normalize)
+        .filter(|it| !it.is_empty())
+}
+
+/// 从 Content-Type 中取 charset，例如 text/html; charset=gbk
+fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .filter_map(|it| it.trim().split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches('"').as_bytes()))
+}
+
+/// 从 <meta charset="gbk"> 或者 <meta http-equiv="Content-Type" content="text/html; charset=gbk"> 中取 charset
+/// 只在前 4KB 中查找，此时还不知道编码，直接在原始字节上匹配
+fn charset_from_meta(body: &[u8]) -> Option<&'static Encoding> {
+    let re = META_CHARSET_REGEX.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_\-:.]+)"#).unwrap()
+    });
+    let head = &body[..body.len().min(4096)];
+    re.captures(head)
+        .and_then(|caps| caps.get(1))
+        .and_then(|it| Encoding::for_label(it.as_bytes()))
+        // meta 中声明 UTF-16 时按照 HTML 规范当作 UTF-8 处理
+        .map(|it| it.output_encoding())
+}
+
+/// 合并空白字符，去掉首尾空白
+fn normalize(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "中文标题" 的 GBK 编码
+    const GBK_TITLE: &[u8] = b"\xd6\xd0\xce\xc4\xb1\xea\xcc\xe2";
+
+    fn page(head: &[u8], title: &[u8]) -> Vec<u8> {
+        [
+            b"<html><head>".as_slice(),
+            head,
+            b"<title>",
+            title,
+            b"</title></head></html>",
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn charset_from_content_type_header() {
+        let body = page(b"", GBK_TITLE);
+        assert_eq!(
+            extract_title(&body, Some("text/html; charset=GBK")).as_deref(),
+            Some("中文标题")
+        );
+        assert_eq!(
+            extract_title(&body, Some(r#"text/html;charset="gb2312""#)).as_deref(),
+            Some("中文标题")
+        );
+    }
+
+    #[test]
+    fn charset_from_meta_tag() {
+        let body = page(br#"<meta charset="gbk">"#, GBK_TITLE);
+        assert_eq!(
+            extract_title(&body, Some("text/html")).as_deref(),
+            Some("中文标题")
+        );
+
+        let body = page(
+            br#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=gb2312">"#,
+            GBK_TITLE,
+        );
+        assert_eq!(extract_title(&body, None).as_deref(), Some("中文标题"));
+    }
+
+    #[test]
+    fn content_type_wins_over_meta() {
+        let body = page(br#"<meta charset="utf-8">"#, GBK_TITLE);
+        assert_eq!(
+            extract_title(&body, Some("text/html; charset=gbk")).as_deref(),
+            Some("中文标题")
+        );
+    }
+
+    #[test]
+    fn meta_charset_detection() {
+        assert_eq!(
+            charset_from_meta(br#"<meta charset='GB2312' />"#).map(|it| it.name()),
+            Some("GBK")
+        );
+        assert_eq!(
+            charset_from_meta(br#"<meta charset="utf-16le">"#).map(|it| it.name()),
+            Some("UTF-8")
+        );
+        assert_eq!(charset_from_meta(br#"<meta charset="unknown">"#), None);
+        assert_eq!(charset_from_meta(b"<html><head></head></html>"), None);
+
+        // 只在前 4KB 中查找
+        let mut body = vec![b' '; 4096];
+        body.extend_from_slice(br#"<meta charset="gbk">"#);
+        assert_eq!(charset_from_meta(&body), None);
+    }
+
+    #[test]
+    fn decodes_entities_and_normalizes_whitespace() {
+        let body = page(
+            b"",
+            b"\n  Tom &amp; Jerry\n\t&lt;Home&gt;  &#20013;&#25991;\n",
+        );
+        assert_eq!(
+            extract_title(&body, None).as_deref(),
+            Some("Tom & Jerry <Home> 中文")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_og_title() {
+        let body = page(
+            br#"<meta property="og:title" content="  Open  Graph ">"#,
+            b"  ",
+        );
+        assert_eq!(extract_title(&body, None).as_deref(), Some("Open Graph"));
+        assert_eq!(
+            extract_title(b"<html><body>no title</body></html>", None),
+            None
+        );
+    }
+}
@@ -1,23 +1,37 @@
 use std::{process::exit, sync::Arc};
 
 use args::AppArgs;
-use resolver::{build_resolver, check_wildcard};
+use log::{debug, info, LevelFilter};
+use log4rs::{
+    append::console::{ConsoleAppender, Target},
+    config::{Appender, Root},
+    encode::pattern::PatternEncoder,
+    Config,
+};
 use tokio::sync::Mutex;
 
 use crate::{
     context::{AppContext, ResolveResult},
-    engines::{resolver, saver, task_builder},
+    engines::{build_resolver, check_wildcard, resolver, saver, task_builder, vhost},
+    enrich::Enricher,
+    output::Differ,
+    scope::Scope,
 };
 
 mod args;
 mod context;
 mod engines;
+mod enrich;
+mod http;
+mod output;
+mod scope;
 
 #[tokio::main]
 async fn main() {
     let mut app_args = AppArgs::default();
     app_args.parse_cli_arguments();
-    println!("app_args: {:?}", app_args);
+    init_logger(app_args.silent);
+    debug!("app_args: {:?}", app_args);
 
     // 初始化消息队列
     // 任务通道
@@ -34,15 +48,46 @@ async fn main() {
             eprintln!("Find wildcard record, IP list: {:?}", e);
             exit(-1);
         } else {
-            println!("No wildcard records.")
+            info!("No wildcard records.")
         }
     }
 
+    // 加载 CDN / 云厂商等离线数据
+    let enricher = match Enricher::load(&app_args).await {
+        Ok(enricher) => Arc::new(enricher),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(-1);
+        }
+    };
+
+    // 加载扫描范围规则
+    let scope = match Scope::load(app_args.scope_path.as_ref()).await {
+        Ok(scope) => Arc::new(scope),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(-1);
+        }
+    };
+
+    // 读取上一次的结果，需要在 saver 打开输出文件之前读取，输出文件可能就是上一次的结果
+    let differ = match app_args.diff_path.as_ref() {
+        Some(path) => match Differ::load(path, &app_args.target) {
+            Ok(differ) => Some(differ),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(-1);
+            }
+        },
+        None => None,
+    };
+
     // 启动 task_builder
     let task_builder = tokio::spawn(task_builder(
         task_tx.clone(),
         app_args.clone(),
         app_context.clone(),
+        scope.clone(),
     ));
 
     // 启动 resolver
@@ -57,15 +102,23 @@ async fn main() {
             idx,
             app_args.clone(),
             app_context.clone(),
+            enricher.clone(),
+            scope.clone(),
         ));
         resolvers.push(h);
     }
 
+    // 开启 vhost 探测时，saver 需要等 vhost 结束
+    if app_args.vhost {
+        app_context.lock().await.vhost_status = context::EngineStatus::Init;
+    }
+
     // 启动 saver
     let saver = tokio::spawn(saver(
         saver_rx.clone(),
         app_context.clone(),
         app_args.clone(),
+        differ,
     ));
 
     // 等待所有任务结束
@@ -73,5 +126,37 @@ async fn main() {
     for h in resolvers {
         let _ = h.await;
     }
+
+    // 解析全部结束后再进行 vhost 探测
+    if app_args.vhost {
+        app_context.lock().await.vhost_status = context::EngineStatus::Running;
+        let _ = tokio::spawn(vhost(
+            saver_tx.clone(),
+            app_args.clone(),
+            app_context.clone(),
+            scope.clone(),
+        ))
+        .await;
+    }
     let _ = saver.await;
 }
+
+/// 运行信息全部输出到 stderr，stdout 只留给扫描结果
+/// silent 模式下只输出警告和错误
+fn init_logger(silent: bool) {
+    let level = if silent {
+        LevelFilter::Warn
+    } else {
+        LevelFilter::Info
+    };
+    let stderr = ConsoleAppender::builder()
+        .target(Target::Stderr)
+        .encoder(Box::new(PatternEncoder::new("{m}{n}")))
+        .build();
+    let config = Config::builder()
+        .appender(Appender::builder().build("stderr", Box::new(stderr)))
+        .build(Root::builder().appender("stderr").build(level));
+    if let Ok(config) = config {
+        let _ = log4rs::init_config(config);
+    }
+}
@@ -0,0 +1,284 @@
+mod by_ip;
+mod diff;
+mod graph;
+mod html;
+mod jsonl;
+mod markdown;
+mod sqlite;
+mod table;
+mod takeover;
+mod template;
+mod text;
+
+pub use diff::Differ;
+pub use table::{Column, JOIN_SEPARATOR};
+pub use template::Template;
+
+use tokio::{
+    fs::File,
+    io::{stdout, AsyncWriteExt, Stdout},
+};
+
+use crate::{
+    args::AppArgs,
+    context::{HttpProbe, ResolveResult},
+};
+use graph::Graph;
+use sqlite::Database;
+
+/// 输出格式
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    // 一行一个结果，字段之间用 " - " 分隔，便于人工查看
+    #[default]
+    Text,
+    // 一行一个 JSON 对象，便于其他工具处理
+    Jsonl,
+    // 带表头的表格，列由 --columns 指定
+    Csv,
+    Tsv,
+    // SQLite 数据库，多次扫描写入同一个文件，保留历史结果
+    Sqlite,
+    // 扫描结束后生成的离线 HTML 报告
+    Html,
+    // 扫描结束后生成的 Markdown 报告，便于贴到工单里
+    Markdown,
+    // 按 IP 反查域名，并按网段和 ASN 聚合
+    ByIp,
+    // 域名、CNAME、IP、ASN 的关系图
+    Dot,
+    GraphMl,
+    GraphJson,
+    // 一行一个域名
+    Hosts,
+    // 使用 --template 指定的模板，一行一个结果
+    Template,
+}
+
+impl OutputFormat {
+    /// 命令行中可以使用的格式名称
+    pub const NAMES: [&'static str; 12] = [
+        "text",
+        "jsonl",
+        "csv",
+        "tsv",
+        "sqlite",
+        "html",
+        "markdown",
+        "by-ip",
+        "dot",
+        "graphml",
+        "graph-json",
+        "hosts",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Self::Text),
+            "jsonl" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "sqlite" => Some(Self::Sqlite),
+            "html" => Some(Self::Html),
+            "markdown" => Some(Self::Markdown),
+            "by-ip" => Some(Self::ByIp),
+            "dot" => Some(Self::Dot),
+            "graphml" => Some(Self::GraphMl),
+            "graph-json" => Some(Self::GraphJson),
+            "hosts" => Some(Self::Hosts),
+            _ => None,
+        }
+    }
+
+    /// 未指定输出路径时使用的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Sqlite => "db",
+            Self::Html => "html",
+            Self::Markdown => "md",
+            Self::ByIp => "ips.txt",
+            Self::Dot => "dot",
+            Self::GraphMl => "graphml",
+            Self::GraphJson => "graph.json",
+            Self::Hosts => "hosts.txt",
+            Self::Template => "txt",
+        }
+    }
+
+    /// 是否一行一个结果，可以边扫描边输出
+    pub fn is_line(&self) -> bool {
+        !self.is_report() && *self != Self::Sqlite
+    }
+
+    /// 是否需要拿到全部结果之后才能生成
+    fn is_report(&self) -> bool {
+        matches!(
+            self,
+            Self::Html | Self::Markdown | Self::ByIp | Self::Dot | Self::GraphMl | Self::GraphJson
+        )
+    }
+}
+
+/// 结果的写入方式，文本类的格式一行一个结果，SQLite 写入数据库
+/// 报告类的格式先收集全部结果，扫描结束时再生成
+pub enum Writer {
+    Line(File),
+    // silent 模式下输出到 stdout，格式可以和输出文件不同
+    Stdout(Stdout, OutputFormat),
+    Sqlite(Database),
+    Report(File, Vec<ResolveResult>),
+}
+
+impl Writer {
+    /// 打开输出文件，文本类的格式会覆盖已有文件
+    pub async fn open(app_args: &AppArgs) -> Result<Self, String> {
+        let output = &app_args.output_path;
+        if app_args.format == OutputFormat::Sqlite {
+            return Database::open(output, &app_args.target).map(Self::Sqlite);
+        }
+
+        let mut output_file = File::create(output)
+            .await
+            .map_err(|e| format!("创建输出文件 {} 失败: {}", output, e))?;
+        if app_args.format.is_report() {
+            return Ok(Self::Report(output_file, vec![]));
+        }
+        if let Some(header) = header(app_args.format, &app_args.columns) {
+            output_file
+                .write_all(header.as_bytes())
+                .await
+                .map_err(|e| format!("写入表头失败: {}", e))?;
+        }
+        Ok(Self::Line(output_file))
+    }
+
+    /// silent 模式下把结果逐行输出到 stdout
+    pub async fn open_stdout(app_args: &AppArgs) -> Result<Option<Self>, String> {
+        let format = match app_args.stdout_format {
+            Some(format) => format,
+            None => return Ok(None),
+        };
+        let mut stdout = stdout();
+        if let Some(header) = header(format, &app_args.columns) {
+            stdout
+                .write_all(header.as_bytes())
+                .await
+                .map_err(|e| format!("write stdout error: {:?}", e))?;
+        }
+        Ok(Some(Self::Stdout(stdout, format)))
+    }
+
+    pub async fn write(
+        &mut self,
+        result: &ResolveResult,
+        app_args: &AppArgs,
+    ) -> Result<(), String> {
+        match self {
+            Self::Line(output_file) => {
+                let line = format_line(result, app_args.format, app_args);
+                output_file
+                    .write_all(line.as_bytes())
+                    .await
+                    .map_err(|e| format!("write file error, value: {:?}, error: {:?}", line, e))
+            }
+            // 每行都 flush，管道另一端可以马上拿到结果
+            Self::Stdout(stdout, format) => {
+                let line = format_line(result, *format, app_args);
+                let written = match stdout.write_all(line.as_bytes()).await {
+                    Ok(_) => stdout.flush().await,
+                    Err(e) => Err(e),
+                };
+                written.map_err(|e| format!("write stdout error: {:?}", e))
+            }
+            Self::Sqlite(database) => database.insert(result),
+            // 已经解析出的域名的 vhost 结果合并到 DNS 结果中，报告中一个域名只有一条
+            Self::Report(_, results) => {
+                let existing = results
+                    .iter_mut()
+                    .find(|it| result.is_vhost() && it.domain == result.domain);
+                match existing {
+                    Some(existing) => existing.merge_vhost(result),
+                    None => results.push(result.clone()),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 扫描结束时调用，报告类的格式在这里生成
+    pub async fn finish(&mut self, app_args: &AppArgs) -> Result<(), String> {
+        match self {
+            Self::Line(output_file) => output_file
+                .flush()
+                .await
+                .map_err(|e| format!("write file error: {:?}", e)),
+            Self::Stdout(stdout, _) => stdout
+                .flush()
+                .await
+                .map_err(|e| format!("write stdout error: {:?}", e)),
+            Self::Sqlite(database) => database.finish(),
+            Self::Report(output_file, results) => {
+                results.sort_by(|a, b| a.domain.cmp(&b.domain));
+                let report = render_report(results, app_args);
+                output_file
+                    .write_all(report.as_bytes())
+                    .await
+                    .map_err(|e| format!("write file error: {:?}", e))
+            }
+        }
+    }
+}
+
+/// 输出文件开头的内容，例如 CSV 的表头
+fn header(format: OutputFormat, columns: &[Column]) -> Option<String> {
+    match format {
+        OutputFormat::Csv => Some(table::header(columns, b',')),
+        OutputFormat::Tsv => Some(table::header(columns, b'\t')),
+        _ => None,
+    }
+}
+
+/// 把一条结果格式化成一行，末尾带换行
+fn format_line(result: &ResolveResult, format: OutputFormat, app_args: &AppArgs) -> String {
+    match format {
+        OutputFormat::Text => text::format_line(result),
+        OutputFormat::Jsonl => jsonl::format_line(result),
+        OutputFormat::Csv => table::format_line(result, &app_args.columns, b','),
+        OutputFormat::Tsv => table::format_line(result, &app_args.columns, b'\t'),
+        OutputFormat::Hosts => format!("{}\n", result.domain),
+        OutputFormat::Template => match &app_args.template {
+            Some(template) => template.render(result),
+            None => unreachable!("template 格式必须指定模板"),
+        },
+        _ => unreachable!("{:?} 不按行输出", format),
+    }
+}
+
+/// 根据全部结果生成报告
+fn render_report(results: &[ResolveResult], app_args: &AppArgs) -> String {
+    match app_args.format {
+        OutputFormat::Html => html::render(&app_args.target, results),
+        OutputFormat::Markdown => markdown::render(&app_args.target, results),
+        OutputFormat::ByIp => by_ip::render(results),
+        OutputFormat::Dot => Graph::build(results).to_dot(),
+        OutputFormat::GraphMl => Graph::build(results).to_graphml(),
+        OutputFormat::GraphJson => Graph::build(results).to_json(),
+        _ => unreachable!("{:?} 不是报告格式", app_args.format),
+    }
+}
+
+/// 一个域名有多个探测结果时，表格等只能放一个值的地方使用的探测结果
+/// 优先取有状态码的 https，其次是有状态码的 http，都失败时取第一个
+pub fn primary_probe(result: &ResolveResult) -> Option<&HttpProbe> {
+    result
+        .https
+        .iter()
+        .chain(result.http.iter())
+        .find(|it| it.code.is_some())
+        .or_else(|| result.https.first())
+        .or_else(|| result.http.first())
+}
@@ -0,0 +1,105 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    net::IpAddr,
+};
+
+use ipnet::IpNet;
+
+use crate::context::ResolveResult;
+
+/// 聚合时 IPv4 使用 /24，IPv6 使用 /64
+const IPV4_PREFIX: u8 = 24;
+const IPV6_PREFIX: u8 = 64;
+
+/// 按 IP 反查域名，并按网段和 ASN 聚合，用于发现共享主机和确定端口扫描的目标
+pub fn render(results: &[ResolveResult]) -> String {
+    // IP 按地址排序，而不是按字符串
+    let mut by_ip: BTreeMap<IpAddr, BTreeSet<&str>> = BTreeMap::new();
+    let mut infos = BTreeMap::new();
+    for result in results {
+        for ip in result.ip.iter() {
+            let Ok(addr) = ip.parse::<IpAddr>() else {
+                continue;
+            };
+            by_ip.entry(addr).or_default().insert(&result.domain);
+            if let Some(info) = result.ip_info.get(ip) {
+                infos.insert(addr, info);
+            }
+        }
+    }
+
+    let mut by_cidr: BTreeMap<IpNet, BTreeMap<IpAddr, &BTreeSet<&str>>> = BTreeMap::new();
+    let mut by_asn: BTreeMap<u32, BTreeMap<IpAddr, &BTreeSet<&str>>> = BTreeMap::new();
+    for (addr, domains) in by_ip.iter() {
+        by_cidr
+            .entry(network(addr))
+            .or_default()
+            .insert(*addr, domains);
+        if let Some(asn) = infos.get(addr).and_then(|it| it.asn) {
+            by_asn.entry(asn).or_default().insert(*addr, domains);
+        }
+    }
+
+    let mut output = String::new();
+    let _ = writeln!(output, "# IP ({})", by_ip.len());
+    for (addr, domains) in by_ip.iter() {
+        let info = infos
+            .get(addr)
+            .map(|it| format!(" [{}]", it))
+            .unwrap_or_default();
+        let _ = writeln!(output, "{}{} ({})", addr, info, domains.len());
+        for domain in domains {
+            let _ = writeln!(output, "    {}", domain);
+        }
+    }
+
+    let _ = writeln!(output, "\n# CIDR ({})", by_cidr.len());
+    for (net, members) in by_cidr.iter() {
+        write_block(&mut output, &net.to_string(), members);
+    }
+
+    // 没有 ASN 数据库时没有这部分
+    if !by_asn.is_empty() {
+        let _ = writeln!(output, "\n# ASN ({})", by_asn.len());
+        for (asn, members) in by_asn.iter() {
+            let org = members
+                .keys()
+                .find_map(|addr| infos.get(addr).and_then(|it| it.org.as_deref()))
+                .map(|it| format!(" {}", it))
+                .unwrap_or_default();
+            write_block(&mut output, &format!("AS{}{}", asn, org), members);
+        }
+    }
+    output
+}
+
+/// 输出一个聚合块，块下面每个 IP 一行，后面跟着解析到这个 IP 的域名
+fn write_block(output: &mut String, name: &str, members: &BTreeMap<IpAddr, &BTreeSet<&str>>) {
+    let domains = members
+        .values()
+        .flat_map(|it| it.iter())
+        .collect::<BTreeSet<_>>();
+    let _ = writeln!(
+        output,
+        "{} ({} IPs, {} domains)",
+        name,
+        members.len(),
+        domains.len()
+    );
+    for (addr, domains) in members.iter() {
+        let domains = domains.iter().copied().collect::<Vec<_>>();
+        let _ = writeln!(output, "    {} {}", addr, domains.join(", "));
+    }
+}
+
+fn network(addr: &IpAddr) -> IpNet {
+    let prefix = match addr {
+        IpAddr::V4(_) => IPV4_PREFIX,
+        IpAddr::V6(_) => IPV6_PREFIX,
+    };
+    // 前缀长度是合法的，不会失败
+    IpNet::new(*addr, prefix)
+        .map(|it| it.trunc())
+        .unwrap_or_else(|_| IpNet::from(*addr))
+}
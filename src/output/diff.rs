@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, fmt::Write, fs::File, io::Read};
+
+use super::{jsonl, primary_probe, sqlite};
+use crate::context::ResolveResult;
+
+/// SQLite 数据库文件的开头
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// 对比时关心的字段
+/// IP 和 CNAME 都排序去重后按集合比较，应答中顺序的变化不算变化
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    ips: Vec<String>,
+    cnames: Vec<String>,
+    status: Option<u16>,
+    title: Option<String>,
+}
+
+impl From<&ResolveResult> for Snapshot {
+    fn from(result: &ResolveResult) -> Self {
+        let probe = primary_probe(result);
+        Self {
+            ips: sorted(&result.ip),
+            cnames: sorted(&result.cname),
+            status: probe.and_then(|it| it.code),
+            title: probe.and_then(|it| it.title.clone()),
+        }
+    }
+}
+
+/// 和上一次扫描的结果对比，找出新增、消失和变化的域名
+pub struct Differ {
+    previous: BTreeMap<String, Snapshot>,
+    current: BTreeMap<String, Snapshot>,
+}
+
+impl Differ {
+    /// 读取上一次的结果，支持 JSON Lines 文件和 SQLite 数据库（取目标最近一次完成的扫描）
+    pub fn load(path: &str, target: &str) -> Result<Self, String> {
+        let mut magic = [0u8; 16];
+        let is_sqlite = File::open(path)
+            .and_then(|mut it| it.read_exact(&mut magic))
+            .is_ok()
+            && magic == SQLITE_MAGIC;
+        let results = if is_sqlite {
+            sqlite::load_latest(path, target)?
+        } else {
+            jsonl::load(path)?
+        };
+
+        let mut previous = BTreeMap::new();
+        for result in results.iter() {
+            insert(&mut previous, result);
+        }
+        Ok(Self {
+            previous,
+            current: BTreeMap::new(),
+        })
+    }
+
+    /// 记录本次扫描的一个结果
+    pub fn record(&mut self, result: &ResolveResult) {
+        insert(&mut self.current, result);
+    }
+
+    /// 生成变化报告，新增的以 + 开头，消失的以 - 开头，变化的以 ~ 开头并列出变化的字段
+    pub fn report(&self) -> String {
+        let mut added = vec![];
+        let mut removed = vec![];
+        let mut changed = vec![];
+        for (domain, current) in self.current.iter() {
+            match self.previous.get(domain) {
+                None => added.push(format!("+ {} {}", domain, describe(current))),
+                Some(previous) if previous != current => {
+                    changed.push(format!("~ {} {}", domain, changes(previous, current)))
+                }
+                _ => {}
+            }
+        }
+        for (domain, previous) in self.previous.iter() {
+            if !self.current.contains_key(domain) {
+                removed.push(format!("- {} {}", domain, describe(previous)));
+            }
+        }
+
+        let mut report = String::new();
+        let _ = writeln!(
+            report,
+            "# new: {}, removed: {}, changed: {}, unchanged: {}",
+            added.len(),
+            removed.len(),
+            changed.len(),
+            self.current.len() - added.len() - changed.len()
+        );
+        for line in added.iter().chain(removed.iter()).chain(changed.iter()) {
+            let _ = writeln!(report, "{}", line);
+        }
+        report
+    }
+}
+
+/// vhost 在解析全部结束后运行，同一个域名的 DNS 结果总是在前面
+/// 已经解析出的域名的 vhost 结果只是额外可以访问的 IP，不参与对比
+fn insert(snapshots: &mut BTreeMap<String, Snapshot>, result: &ResolveResult) {
+    if result.is_vhost() && snapshots.contains_key(&result.domain) {
+        return;
+    }
+    snapshots.insert(result.domain.to_owned(), Snapshot::from(result));
+}
+
+fn sorted(values: &[String]) -> Vec<String> {
+    let mut values = values.to_vec();
+    values.sort();
+    values.dedup();
+    values
+}
+
+fn describe(snapshot: &Snapshot) -> String {
+    format!(
+        "{:?} {:?} {} {:?}",
+        snapshot.ips,
+        snapshot.cnames,
+        format_status(snapshot.status),
+        snapshot.title.as_deref().unwrap_or_default()
+    )
+}
+
+fn changes(previous: &Snapshot, current: &Snapshot) -> String {
+    let mut changes = vec![];
+    if previous.ips != current.ips {
+        changes.push(format!("ip: {:?} -> {:?}", previous.ips, current.ips));
+    }
+    if previous.cnames != current.cnames {
+        changes.push(format!(
+            "cname: {:?} -> {:?}",
+            previous.cnames, current.cnames
+        ));
+    }
+    if previous.status != current.status {
+        changes.push(format!(
+            "status: {} -> {}",
+            format_status(previous.status),
+            format_status(current.status)
+        ));
+    }
+    if previous.title != current.title {
+        changes.push(format!(
+            "title: {:?} -> {:?}",
+            previous.title.as_deref().unwrap_or_default(),
+            current.title.as_deref().unwrap_or_default()
+        ));
+    }
+    changes.join("; ")
+}
+
+fn format_status(status: Option<u16>) -> String {
+    status.map_or("-".to_owned(), |it| it.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{HttpProbe, VHOST_SOURCE};
+
+    fn result(domain: &str, ips: &[&str], cnames: &[&str], code: Option<u16>) -> ResolveResult {
+        ResolveResult {
+            domain: domain.to_owned(),
+            ip: ips.iter().map(|it| it.to_string()).collect(),
+            cname: cnames.iter().map(|it| it.to_string()).collect(),
+            https: vec![HttpProbe {
+                code,
+                title: code.map(|_| "Home".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn differ(previous: &[ResolveResult]) -> Differ {
+        Differ {
+            previous: previous
+                .iter()
+                .map(|it| (it.domain.to_owned(), Snapshot::from(it)))
+                .collect(),
+            current: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_changed_and_unchanged() {
+        let mut differ = differ(&[
+            result("a.example.com", &["1.1.1.1"], &[], Some(200)),
+            result("b.example.com", &["2.2.2.2"], &[], Some(200)),
+            result("c.example.com", &["3.3.3.3"], &[], Some(200)),
+        ]);
+        differ.record(&result("a.example.com", &["1.1.1.1"], &[], Some(200)));
+        differ.record(&result("b.example.com", &["2.2.2.3"], &[], Some(500)));
+        differ.record(&result("d.example.com", &["4.4.4.4"], &[], None));
+
+        assert_eq!(
+            differ.report(),
+            concat!(
+                "# new: 1, removed: 1, changed: 1, unchanged: 1\n",
+                "+ d.example.com [\"4.4.4.4\"] [] - \"\"\n",
+                "- c.example.com [\"3.3.3.3\"] [] 200 \"Home\"\n",
+                "~ b.example.com ip: [\"2.2.2.2\"] -> [\"2.2.2.3\"]; status: 200 -> 500\n",
+            )
+        );
+    }
+
+    #[test]
+    fn order_of_ips_and_cnames_is_ignored() {
+        let mut differ = differ(&[result(
+            "a.example.com",
+            &["1.1.1.1", "2.2.2.2"],
+            &["x.cdn.net", "y.cdn.net"],
+            Some(200),
+        )]);
+        differ.record(&result(
+            "a.example.com",
+            &["2.2.2.2", "1.1.1.1", "1.1.1.1"],
+            &["y.cdn.net", "x.cdn.net"],
+            Some(200),
+        ));
+        assert_eq!(
+            differ.report(),
+            "# new: 0, removed: 0, changed: 0, unchanged: 1\n"
+        );
+    }
+
+    #[test]
+    fn vhost_results_of_resolved_domains_are_ignored() {
+        let mut differ = differ(&[result("a.example.com", &["1.1.1.1"], &[], Some(200))]);
+        differ.record(&result("a.example.com", &["1.1.1.1"], &[], Some(200)));
+        let mut vhost = result("a.example.com", &["9.9.9.9"], &[], Some(404));
+        vhost.source = Some(VHOST_SOURCE.to_owned());
+        differ.record(&vhost);
+        vhost.domain = "b.example.com".to_owned();
+        differ.record(&vhost);
+        assert_eq!(
+            differ.report(),
+            concat!(
+                "# new: 1, removed: 0, changed: 0, unchanged: 1\n",
+                "+ b.example.com [\"9.9.9.9\"] [] 404 \"Home\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn reports_cname_and_title_changes() {
+        let mut differ = differ(&[result("a.example.com", &[], &["x.cdn.net"], Some(200))]);
+        let mut current = result("a.example.com", &[], &["y.cdn.net"], Some(200));
+        current.https[0].title = Some("Login".to_owned());
+        differ.record(&current);
+        assert_eq!(
+            differ.report(),
+            concat!(
+                "# new: 0, removed: 0, changed: 1, unchanged: 0\n",
+                "~ a.example.com cname: [\"x.cdn.net\"] -> [\"y.cdn.net\"]; title: \"Home\" -> \"Login\"\n",
+            )
+        );
+    }
+}
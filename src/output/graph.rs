@@ -0,0 +1,203 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use serde::Serialize;
+
+use crate::context::ResolveResult;
+
+/// 节点，同一个名字只有一个节点，多个域名共用的 CNAME、IP、ASN 会连到同一个节点上
+#[derive(Serialize)]
+struct Node {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    label: String,
+}
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct Edge {
+    source: String,
+    target: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+/// 域名 -> CNAME 链 -> IP -> ASN 的关系图
+#[derive(Serialize)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    pub fn build(results: &[ResolveResult]) -> Self {
+        let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+        let mut edges: BTreeSet<Edge> = BTreeSet::new();
+        let mut add_node = |id: &str, kind: &'static str, label: &str| {
+            // 扫描出的域名同时也是别人的 CNAME 时，以域名为准
+            let node = nodes.entry(id.to_owned()).or_insert_with(|| Node {
+                id: id.to_owned(),
+                kind,
+                label: label.to_owned(),
+            });
+            if kind == "domain" {
+                node.kind = kind;
+            }
+        };
+
+        for result in results {
+            let domain = normalize(&result.domain);
+            add_node(&domain, "domain", &domain);
+
+            // CNAME 按应答中的顺序连成链，链的最后一跳指向 IP
+            // result.cname 只有第一跳，完整的链在 records 里
+            let mut last = domain;
+            for cname in cname_chain(result) {
+                add_node(&cname, "cname", &cname);
+                edges.insert(Edge {
+                    source: last,
+                    target: cname.to_owned(),
+                    kind: "cname",
+                });
+                last = cname;
+            }
+
+            for ip in result.ip.iter() {
+                let ip_id = format!("ip:{}", ip);
+                add_node(&ip_id, "ip", ip);
+                edges.insert(Edge {
+                    source: last.to_owned(),
+                    target: ip_id.to_owned(),
+                    kind: "resolves",
+                });
+
+                let info = result.ip_info.get(ip);
+                if let Some(asn) = info.and_then(|it| it.asn) {
+                    let asn_id = format!("asn:{}", asn);
+                    let label = match info.and_then(|it| it.org.as_deref()) {
+                        Some(org) => format!("AS{} {}", asn, org),
+                        None => format!("AS{}", asn),
+                    };
+                    add_node(&asn_id, "asn", &label);
+                    edges.insert(Edge {
+                        source: ip_id,
+                        target: asn_id,
+                        kind: "announced_by",
+                    });
+                }
+            }
+        }
+
+        Self {
+            nodes: nodes.into_values().collect(),
+            edges: edges.into_iter().collect(),
+        }
+    }
+
+    /// Graphviz DOT 格式
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph subdomains {\n    rankdir=LR;\n");
+        for node in self.nodes.iter() {
+            let shape = match node.kind {
+                "domain" => "box",
+                "cname" => "ellipse",
+                "ip" => "diamond",
+                _ => "hexagon",
+            };
+            let _ = writeln!(
+                dot,
+                "    {} [label={}, shape={}, type={}];",
+                dot_quote(&node.id),
+                dot_quote(&node.label),
+                shape,
+                node.kind
+            );
+        }
+        for edge in self.edges.iter() {
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [type={}];",
+                dot_quote(&edge.source),
+                dot_quote(&edge.target),
+                edge.kind
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// GraphML 格式，Gephi 和 Neo4j（APOC）可以直接导入
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n",
+            "  <key id=\"relation\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n",
+            "  <graph id=\"subdomains\" edgedefault=\"directed\">\n",
+        ));
+        for node in self.nodes.iter() {
+            let _ = writeln!(
+                xml,
+                "    <node id=\"{}\"><data key=\"label\">{}</data><data key=\"type\">{}</data></node>",
+                xml_escape(&node.id),
+                xml_escape(&node.label),
+                node.kind
+            );
+        }
+        for edge in self.edges.iter() {
+            let _ = writeln!(
+                xml,
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"relation\">{}</data></edge>",
+                xml_escape(&edge.source),
+                xml_escape(&edge.target),
+                edge.kind
+            );
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// {"nodes": [...], "edges": [...]} 格式的 JSON
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).unwrap_or_default();
+        json.push('\n');
+        json
+    }
+}
+
+/// 按应答顺序取出 CNAME 链，没有记录 records 时退回到 result.cname
+fn cname_chain(result: &ResolveResult) -> Vec<String> {
+    let mut chain: Vec<String> = vec![];
+    let records = result
+        .records
+        .iter()
+        .filter(|it| it.record_type == "CNAME")
+        .map(|it| &it.value);
+    for cname in records.chain(result.cname.iter()) {
+        let cname = normalize(cname);
+        if !chain.contains(&cname) {
+            chain.push(cname);
+        }
+    }
+    chain
+}
+
+/// 域名统一小写，去掉末尾的点
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_lowercase()
+}
+
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use super::{primary_probe, takeover::takeover_reason};
+use crate::context::ResolveResult;
+
+/// 样式和脚本直接写在页面里，生成的文件可以离线打开
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", "Microsoft YaHei", sans-serif; margin: 24px; color: #222; }
+h1 { font-size: 22px; }
+h2 { font-size: 18px; margin-top: 32px; border-bottom: 1px solid #ddd; padding-bottom: 4px; }
+.stats { display: flex; flex-wrap: wrap; gap: 12px; }
+.stat { border: 1px solid #ddd; border-radius: 4px; padding: 8px 16px; min-width: 100px; }
+.stat b { display: block; font-size: 20px; }
+table { border-collapse: collapse; width: 100%; font-size: 13px; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f5f5f5; }
+table.sortable th { cursor: pointer; user-select: none; }
+table.sortable th.asc::after { content: " \25B2"; }
+table.sortable th.desc::after { content: " \25BC"; }
+tr.takeover td { background: #fdecea; }
+.filter { margin: 8px 0; padding: 4px 8px; width: 320px; }
+"#;
+
+/// 点击表头排序，数字按数值比较；输入框按行内容过滤
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  table.querySelectorAll("th").forEach(function (th, idx) {
+    th.addEventListener("click", function () {
+      var asc = !th.classList.contains("asc");
+      table.querySelectorAll("th").forEach(function (it) { it.classList.remove("asc", "desc"); });
+      th.classList.add(asc ? "asc" : "desc");
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      rows.sort(function (a, b) {
+        var x = a.cells[idx].textContent, y = b.cells[idx].textContent;
+        var nx = parseFloat(x), ny = parseFloat(y);
+        var r = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+        return asc ? r : -r;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+document.querySelectorAll("input.filter").forEach(function (input) {
+  var table = document.getElementById(input.dataset.table);
+  input.addEventListener("input", function () {
+    var keyword = input.value.toLowerCase();
+    Array.prototype.forEach.call(table.tBodies[0].rows, function (row) {
+      row.style.display = row.textContent.toLowerCase().indexOf(keyword) >= 0 ? "" : "none";
+    });
+  });
+});
+"#;
+
+/// 生成完整的 HTML 报告
+pub fn render(target: &str, results: &[ResolveResult]) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} 子域名扫描报告</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{} 子域名扫描报告</h1>\n",
+        escape(target),
+        STYLE,
+        escape(target)
+    );
+    render_stats(&mut html, results);
+    render_takeovers(&mut html, results);
+    render_hosts(&mut html, results);
+    render_by_ip(&mut html, results);
+    render_by_provider(&mut html, results);
+    let _ = write!(html, "<script>{}</script>\n</body>\n</html>\n", SCRIPT);
+    html
+}
+
+fn render_stats(html: &mut String, results: &[ResolveResult]) {
+    let ips = results
+        .iter()
+        .flat_map(|it| it.ip.iter())
+        .collect::<BTreeSet<_>>();
+    let providers = results
+        .iter()
+        .filter_map(|it| it.provider.as_ref())
+        .collect::<BTreeSet<_>>();
+    let stats = [
+        ("域名", results.len()),
+        ("IP", ips.len()),
+        (
+            "HTTP 存活",
+            results
+                .iter()
+                .filter(|it| primary_probe(it).and_then(|it| it.code).is_some())
+                .count(),
+        ),
+        ("CDN", results.iter().filter(|it| it.cdn).count()),
+        ("厂商", providers.len()),
+        (
+            "疑似接管",
+            results
+                .iter()
+                .filter(|it| takeover_reason(it).is_some())
+                .count(),
+        ),
+    ];
+
+    html.push_str("<h2>概览</h2>\n<div class=\"stats\">\n");
+    for (name, count) in stats {
+        let _ = writeln!(
+            html,
+            "<div class=\"stat\"><b>{}</b>{}</div>",
+            count,
+            escape(name)
+        );
+    }
+    html.push_str("</div>\n");
+}
+
+fn render_takeovers(html: &mut String, results: &[ResolveResult]) {
+    let candidates = results
+        .iter()
+        .filter_map(|it| takeover_reason(it).map(|reason| (it, reason)))
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return;
+    }
+
+    html.push_str("<h2>疑似子域名接管</h2>\n<table>\n<thead><tr><th>域名</th><th>CNAME</th><th>原因</th></tr></thead>\n<tbody>\n");
+    for (result, reason) in candidates {
+        let _ = writeln!(
+            html,
+            "<tr class=\"takeover\"><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&result.domain),
+            join_lines(&result.cname),
+            escape(&reason)
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn render_hosts(html: &mut String, results: &[ResolveResult]) {
+    html.push_str(
+        "<h2>域名列表</h2>\n<input class=\"filter\" data-table=\"hosts\" placeholder=\"过滤\">\n",
+    );
+    html.push_str("<table id=\"hosts\" class=\"sortable\">\n<thead><tr><th>域名</th><th>IP</th><th>CNAME</th><th>状态码</th><th>标题</th><th>URL</th><th>厂商</th><th>技术栈</th><th>来源</th></tr></thead>\n<tbody>\n");
+    for result in results {
+        let probe = primary_probe(result);
+        let class = if takeover_reason(result).is_some() {
+            " class=\"takeover\""
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            html,
+            "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            escape(&result.domain),
+            join_lines(&result.ip),
+            join_lines(&result.cname),
+            probe
+                .and_then(|it| it.code)
+                .map(|it| it.to_string())
+                .unwrap_or_default(),
+            escape(probe.and_then(|it| it.title.as_deref()).unwrap_or_default()),
+            escape(probe.map(|it| it.final_url.as_str()).unwrap_or_default()),
+            escape(result.provider.as_deref().unwrap_or_default()),
+            probe
+                .map(|it| join_lines(&it.technologies))
+                .unwrap_or_default(),
+            escape(result.source.as_deref().unwrap_or_default())
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn render_by_ip(html: &mut String, results: &[ResolveResult]) {
+    let mut groups: BTreeMap<&str, Vec<&ResolveResult>> = BTreeMap::new();
+    for result in results {
+        for ip in result.ip.iter() {
+            groups.entry(ip).or_default().push(result);
+        }
+    }
+
+    html.push_str(
+        "<h2>按 IP 分组</h2>\n<input class=\"filter\" data-table=\"by-ip\" placeholder=\"过滤\">\n",
+    );
+    html.push_str("<table id=\"by-ip\" class=\"sortable\">\n<thead><tr><th>IP</th><th>ASN / 组织 / 国家</th><th>域名数量</th><th>域名</th></tr></thead>\n<tbody>\n");
+    for (ip, members) in groups {
+        let info = members
+            .iter()
+            .find_map(|it| it.ip_info.get(ip))
+            .map(|it| it.to_string())
+            .unwrap_or_default();
+        let domains = members
+            .iter()
+            .map(|it| it.domain.to_owned())
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(ip),
+            escape(&info),
+            domains.len(),
+            join_lines(&domains)
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn render_by_provider(html: &mut String, results: &[ResolveResult]) {
+    let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for result in results {
+        let provider = result.provider.as_deref().unwrap_or("未识别");
+        groups.entry(provider).or_default().push(&result.domain);
+    }
+
+    html.push_str("<h2>按厂商分组</h2>\n<table class=\"sortable\">\n<thead><tr><th>厂商</th><th>域名数量</th><th>域名</th></tr></thead>\n<tbody>\n");
+    for (provider, domains) in groups {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(provider),
+            domains.len(),
+            domains
+                .iter()
+                .map(|it| escape(it))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+/// 多个值每个一行
+fn join_lines(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|it| escape(it))
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+/// 转义 HTML 特殊字符，标题等内容来自目标网站，不能直接输出
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,57 @@
+use std::fs::read_to_string;
+
+use serde::{Deserialize, Serialize};
+
+use crate::context::ResolveResult;
+
+/// JSON Lines 输出的格式版本，字段有不兼容的修改时加一
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Record<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    result: &'a ResolveResult,
+}
+
+pub fn format_line(result: &ResolveResult) -> String {
+    let record = Record {
+        schema_version: SCHEMA_VERSION,
+        result,
+    };
+    // 结构体里只有字符串、数字和字符串为 key 的 map，序列化不会失败
+    let mut line = serde_json::to_string(&record).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+#[derive(Deserialize)]
+struct OwnedRecord {
+    schema_version: u32,
+    #[serde(flatten)]
+    result: ResolveResult,
+}
+
+/// 读取之前输出的 JSON Lines 文件，空行跳过，版本不一致时报错
+pub fn load(path: &str) -> Result<Vec<ResolveResult>, String> {
+    let content =
+        read_to_string(path).map_err(|e| format!("读取 JSONL 文件 {} 失败: {}", path, e))?;
+    let mut results = vec![];
+    for (idx, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str::<OwnedRecord>(line)
+            .map_err(|e| format!("JSONL 文件第 {} 行格式有误: {}", idx + 1, e))?;
+        if record.schema_version != SCHEMA_VERSION {
+            return Err(format!(
+                "JSONL 文件第 {} 行的 schema_version 为 {}，当前版本为 {}",
+                idx + 1,
+                record.schema_version,
+                SCHEMA_VERSION
+            ));
+        }
+        results.push(record.result);
+    }
+    Ok(results)
+}
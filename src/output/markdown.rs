@@ -0,0 +1,141 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use super::{primary_probe, takeover::takeover_reason};
+use crate::context::ResolveResult;
+
+/// 生成 Markdown 报告，可以直接贴到工单里
+pub fn render(target: &str, results: &[ResolveResult]) -> String {
+    let mut md = String::new();
+    let _ = writeln!(md, "# {} 子域名扫描报告\n", target);
+    let _ = writeln!(
+        md,
+        "共 {} 个域名，{} 个 IP，{} 个有 HTTP 响应。\n",
+        results.len(),
+        results
+            .iter()
+            .flat_map(|it| it.ip.iter())
+            .collect::<BTreeSet<_>>()
+            .len(),
+        results.iter().filter(|it| status(it).is_some()).count()
+    );
+    render_findings(&mut md, results);
+    render_by_status(&mut md, results);
+    render_by_ip(&mut md, results);
+    md
+}
+
+/// 值得关注的发现：疑似接管、证书问题、跳出主域的跳转和虚拟主机
+fn render_findings(md: &mut String, results: &[ResolveResult]) {
+    md.push_str("## 值得关注\n\n");
+    let mut count = 0;
+    let mut findings = vec![];
+    for result in results {
+        if let Some(reason) = takeover_reason(result) {
+            findings.push(format!("疑似子域名接管: {}", reason));
+        }
+        let probes = result.https.iter().chain(result.http.iter());
+        for probe in probes {
+            if let Some(certificate) = &probe.certificate {
+                if certificate.expired {
+                    findings.push(format!(
+                        "证书已过期: {} ({})",
+                        probe.url, certificate.not_after
+                    ));
+                }
+                if certificate.self_signed {
+                    findings.push(format!("自签名证书: {}", probe.url));
+                }
+            }
+            if let Some(location) = &probe.offsite_redirect {
+                findings.push(format!("跳转到主域之外: {} -> {}", probe.url, location));
+            }
+        }
+        if result.is_vhost() {
+            findings.push(format!(
+                "虚拟主机: 只能通过 IP {} 访问",
+                result.ip.join(", ")
+            ));
+        }
+        for finding in findings.drain(..) {
+            let _ = writeln!(md, "- `{}` {}", result.domain, finding);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        md.push_str("无\n");
+    }
+    md.push('\n');
+}
+
+fn render_by_status(md: &mut String, results: &[ResolveResult]) {
+    let mut groups: BTreeMap<String, Vec<&ResolveResult>> = BTreeMap::new();
+    for result in results {
+        let class = match status(result) {
+            Some(code) => format!("{}xx", code / 100),
+            None => "无 HTTP 响应".to_owned(),
+        };
+        groups.entry(class).or_default().push(result);
+    }
+
+    md.push_str("## 按状态码分组\n\n");
+    for (class, members) in groups {
+        let _ = writeln!(md, "### {} ({})\n", class, members.len());
+        md.push_str("| 域名 | IP | 状态码 | 标题 |\n| --- | --- | --- | --- |\n");
+        for result in members {
+            let probe = primary_probe(result);
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} |",
+                cell(&result.domain),
+                cell(&result.ip.join(", ")),
+                status(result).map(|it| it.to_string()).unwrap_or_default(),
+                cell(probe.and_then(|it| it.title.as_deref()).unwrap_or_default())
+            );
+        }
+        md.push('\n');
+    }
+}
+
+fn render_by_ip(md: &mut String, results: &[ResolveResult]) {
+    let mut groups: BTreeMap<&str, Vec<&ResolveResult>> = BTreeMap::new();
+    for result in results {
+        for ip in result.ip.iter() {
+            groups.entry(ip).or_default().push(result);
+        }
+    }
+
+    md.push_str(
+        "## 按 IP 分组\n\n| IP | ASN / 组织 / 国家 | 数量 | 域名 |\n| --- | --- | --- | --- |\n",
+    );
+    for (ip, members) in groups {
+        let info = members
+            .iter()
+            .find_map(|it| it.ip_info.get(ip))
+            .map(|it| it.to_string())
+            .unwrap_or_default();
+        let domains = members
+            .iter()
+            .map(|it| it.domain.as_str())
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} | {} |",
+            ip,
+            cell(&info),
+            domains.len(),
+            cell(&domains.join(", "))
+        );
+    }
+}
+
+fn status(result: &ResolveResult) -> Option<u16> {
+    primary_probe(result).and_then(|it| it.code)
+}
+
+/// 表格单元格中不能有 | 和换行
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
@@ -0,0 +1,250 @@
+use rusqlite::{params, Connection};
+
+use crate::context::{HttpProbe, ResolveResult};
+
+/// 数据库结构，每次扫描在 scans 中新增一条记录，结果都关联到这次扫描
+/// ips、cnames、technologies 等多值字段保存为 JSON 数组，可以用 json_each 查询
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    target TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT
+);
+CREATE TABLE IF NOT EXISTS hosts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    scan_id INTEGER NOT NULL REFERENCES scans(id),
+    domain TEXT NOT NULL,
+    ips TEXT NOT NULL,
+    cnames TEXT NOT NULL,
+    nameserver TEXT,
+    rcode TEXT,
+    latency_ms INTEGER NOT NULL,
+    source TEXT,
+    provider TEXT,
+    cdn INTEGER NOT NULL,
+    ip_info TEXT NOT NULL,
+    ecs TEXT NOT NULL,
+    found_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS hosts_scan_domain ON hosts(scan_id, domain);
+CREATE TABLE IF NOT EXISTS records (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    host_id INTEGER NOT NULL REFERENCES hosts(id),
+    record_type TEXT NOT NULL,
+    value TEXT NOT NULL,
+    ttl INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS records_host ON records(host_id);
+CREATE TABLE IF NOT EXISTS http_probes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    host_id INTEGER NOT NULL REFERENCES hosts(id),
+    scheme TEXT NOT NULL,
+    url TEXT NOT NULL,
+    final_url TEXT NOT NULL,
+    code INTEGER,
+    title TEXT,
+    error TEXT,
+    redirects TEXT NOT NULL,
+    offsite_redirect TEXT,
+    server TEXT,
+    powered_by TEXT,
+    content_length INTEGER,
+    body_hash TEXT,
+    favicon_hash INTEGER,
+    technologies TEXT NOT NULL,
+    certificate TEXT
+);
+CREATE INDEX IF NOT EXISTS http_probes_host ON http_probes(host_id);
+";
+
+/// 当前时间，UTC，ISO 8601 格式
+const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')";
+
+/// SQLite 结果库，同一个文件可以保存多次扫描的结果
+pub struct Database {
+    conn: Connection,
+    scan_id: i64,
+}
+
+impl Database {
+    /// 打开或者创建数据库，并新建一次扫描
+    pub fn open(path: &str, target: &str) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| format!("打开数据库失败: {}", e))?;
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("初始化数据库失败: {}", e))?;
+        conn.execute(
+            &format!(
+                "INSERT INTO scans (target, started_at) VALUES (?1, {})",
+                NOW
+            ),
+            params![target],
+        )
+        .map_err(|e| format!("创建扫描记录失败: {}", e))?;
+        let scan_id = conn.last_insert_rowid();
+        Ok(Self { conn, scan_id })
+    }
+
+    /// 写入一个域名的结果，域名、记录和探测结果在同一个事务中写入
+    pub fn insert(&mut self, result: &ResolveResult) -> Result<(), String> {
+        self.insert_host(result)
+            .map_err(|e| format!("写入 {} 失败: {}", result.domain, e))
+    }
+
+    fn insert_host(&mut self, result: &ResolveResult) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            &format!(
+                "INSERT INTO hosts (scan_id, domain, ips, cnames, nameserver, rcode, latency_ms,
+                 source, provider, cdn, ip_info, ecs, found_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, {})",
+                NOW
+            ),
+            params![
+                self.scan_id,
+                result.domain,
+                to_json(&result.ip),
+                to_json(&result.cname),
+                result.nameserver,
+                result.rcode,
+                result.latency_ms,
+                result.source,
+                result.provider,
+                result.cdn,
+                to_json(&result.ip_info),
+                to_json(&result.ecs),
+            ],
+        )?;
+        let host_id = tx.last_insert_rowid();
+
+        for record in result.records.iter() {
+            tx.execute(
+                "INSERT INTO records (host_id, record_type, value, ttl) VALUES (?1, ?2, ?3, ?4)",
+                params![host_id, record.record_type, record.value, record.ttl],
+            )?;
+        }
+
+        let probes = result
+            .http
+            .iter()
+            .map(|it| ("http", it))
+            .chain(result.https.iter().map(|it| ("https", it)));
+        for (scheme, probe) in probes {
+            insert_probe(&tx, host_id, scheme, probe)?;
+        }
+
+        tx.commit()
+    }
+
+    /// 记录扫描结束时间
+    pub fn finish(&self) -> Result<(), String> {
+        self.conn
+            .execute(
+                &format!("UPDATE scans SET finished_at = {} WHERE id = ?1", NOW),
+                params![self.scan_id],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("更新扫描记录失败: {}", e))
+    }
+}
+
+/// 读取数据库中目标最近一次完成的扫描，只恢复域名、IP、CNAME 和 HTTP 探测的主要字段
+/// 需要在本次扫描创建之前调用
+pub fn load_latest(path: &str, target: &str) -> Result<Vec<ResolveResult>, String> {
+    let conn = Connection::open(path).map_err(|e| format!("打开数据库失败: {}", e))?;
+    load_scan(&conn, target).map_err(|e| format!("读取扫描记录失败: {}", e))
+}
+
+fn load_scan(conn: &Connection, target: &str) -> rusqlite::Result<Vec<ResolveResult>> {
+    let scan_id = conn.query_row(
+        "SELECT id FROM scans WHERE target = ?1 AND finished_at IS NOT NULL
+         ORDER BY id DESC LIMIT 1",
+        params![target],
+        |row| row.get::<_, i64>(0),
+    );
+    let scan_id = match scan_id {
+        Ok(scan_id) => scan_id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut results = vec![];
+    let mut host_stmt =
+        conn.prepare("SELECT id, domain, ips, cnames FROM hosts WHERE scan_id = ?1")?;
+    let mut probe_stmt = conn.prepare(
+        "SELECT scheme, url, final_url, code, title, error FROM http_probes
+         WHERE host_id = ?1 ORDER BY id",
+    )?;
+    let hosts = host_stmt.query_map(params![scan_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+    for host in hosts {
+        let (host_id, domain, ips, cnames) = host?;
+        let mut result = ResolveResult {
+            domain,
+            ip: serde_json::from_str(&ips).unwrap_or_default(),
+            cname: serde_json::from_str(&cnames).unwrap_or_default(),
+            ..Default::default()
+        };
+        let probes = probe_stmt.query_map(params![host_id], |row| {
+            let probe = HttpProbe {
+                url: row.get(1)?,
+                final_url: row.get(2)?,
+                code: row.get(3)?,
+                title: row.get(4)?,
+                error: row.get(5)?,
+                ..Default::default()
+            };
+            Ok((row.get::<_, String>(0)?, probe))
+        })?;
+        for probe in probes {
+            match probe? {
+                (scheme, probe) if scheme == "https" => result.https.push(probe),
+                (_, probe) => result.http.push(probe),
+            }
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn insert_probe(
+    conn: &Connection,
+    host_id: i64,
+    scheme: &str,
+    probe: &HttpProbe,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO http_probes (host_id, scheme, url, final_url, code, title, error, redirects,
+         offsite_redirect, server, powered_by, content_length, body_hash, favicon_hash,
+         technologies, certificate)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        params![
+            host_id,
+            scheme,
+            probe.url,
+            probe.final_url,
+            probe.code,
+            probe.title,
+            probe.error,
+            to_json(&probe.redirects),
+            probe.offsite_redirect,
+            probe.server,
+            probe.powered_by,
+            probe.content_length,
+            probe.body_hash,
+            probe.favicon_hash,
+            to_json(&probe.technologies),
+            probe.certificate.as_ref().map(to_json),
+        ],
+    )
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
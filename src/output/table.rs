@@ -0,0 +1,316 @@
+use itertools::Itertools;
+
+use super::primary_probe;
+use crate::context::{HttpProbe, ResolveResult};
+
+/// 多个值的字段（例如多个 IP）使用的分隔符
+pub const JOIN_SEPARATOR: &str = ";";
+
+/// CSV / TSV 中可以输出的列
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Domain,
+    Ips,
+    Cnames,
+    Status,
+    Title,
+    HttpStatus,
+    HttpsStatus,
+    HttpTitle,
+    HttpsTitle,
+    HttpError,
+    HttpsError,
+    Url,
+    Server,
+    Technologies,
+    Provider,
+    Cdn,
+    Asn,
+    Org,
+    Country,
+    Rcode,
+    Ttl,
+    Nameserver,
+    Latency,
+    Source,
+}
+
+impl Column {
+    /// 命令行中可以使用的列名
+    pub const NAMES: [&'static str; 24] = [
+        "domain",
+        "ips",
+        "cnames",
+        "status",
+        "title",
+        "http_status",
+        "https_status",
+        "http_title",
+        "https_title",
+        "http_error",
+        "https_error",
+        "url",
+        "server",
+        "technologies",
+        "provider",
+        "cdn",
+        "asn",
+        "org",
+        "country",
+        "rcode",
+        "ttl",
+        "nameserver",
+        "latency",
+        "source",
+    ];
+
+    /// 未指定 --columns 时输出的列
+    pub const DEFAULT: &'static str = "domain,ips,cnames,status,title,provider";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let column = match name {
+            "domain" => Self::Domain,
+            "ips" => Self::Ips,
+            "cnames" => Self::Cnames,
+            "status" => Self::Status,
+            "title" => Self::Title,
+            "http_status" => Self::HttpStatus,
+            "https_status" => Self::HttpsStatus,
+            "http_title" => Self::HttpTitle,
+            "https_title" => Self::HttpsTitle,
+            "http_error" => Self::HttpError,
+            "https_error" => Self::HttpsError,
+            "url" => Self::Url,
+            "server" => Self::Server,
+            "technologies" => Self::Technologies,
+            "provider" => Self::Provider,
+            "cdn" => Self::Cdn,
+            "asn" => Self::Asn,
+            "org" => Self::Org,
+            "country" => Self::Country,
+            "rcode" => Self::Rcode,
+            "ttl" => Self::Ttl,
+            "nameserver" => Self::Nameserver,
+            "latency" => Self::Latency,
+            "source" => Self::Source,
+            _ => return None,
+        };
+        Some(column)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Domain => "domain",
+            Self::Ips => "ips",
+            Self::Cnames => "cnames",
+            Self::Status => "status",
+            Self::Title => "title",
+            Self::HttpStatus => "http_status",
+            Self::HttpsStatus => "https_status",
+            Self::HttpTitle => "http_title",
+            Self::HttpsTitle => "https_title",
+            Self::HttpError => "http_error",
+            Self::HttpsError => "https_error",
+            Self::Url => "url",
+            Self::Server => "server",
+            Self::Technologies => "technologies",
+            Self::Provider => "provider",
+            Self::Cdn => "cdn",
+            Self::Asn => "asn",
+            Self::Org => "org",
+            Self::Country => "country",
+            Self::Rcode => "rcode",
+            Self::Ttl => "ttl",
+            Self::Nameserver => "nameserver",
+            Self::Latency => "latency",
+            Self::Source => "source",
+        }
+    }
+
+    /// 取结果中这一列的值，多个值的字段使用 JOIN_SEPARATOR 拼接
+    fn value(&self, result: &ResolveResult) -> String {
+        self.values(result).join(JOIN_SEPARATOR)
+    }
+
+    /// 取结果中这一列的全部值，没有值时为空，HTTP 相关的列取主要的探测结果
+    /// http_* / https_* 取对应协议每个端口的探测结果，每个探测一个值，没有时为空，各列按位置对应
+    /// ttl 取所有记录中最小的 TTL
+    /// asn / org / country 按 IP 的顺序去重
+    pub fn values(&self, result: &ResolveResult) -> Vec<String> {
+        let probe = primary_probe(result);
+        let ip_infos = || result.ip.iter().filter_map(|ip| result.ip_info.get(ip));
+        match self {
+            Self::Domain => vec![result.domain.to_owned()],
+            Self::Ips => result.ip.clone(),
+            Self::Cnames => result.cname.clone(),
+            Self::Status => probe
+                .and_then(|it| it.code)
+                .map(|it| it.to_string())
+                .into_iter()
+                .collect(),
+            Self::Title => probe
+                .and_then(|it| it.title.to_owned())
+                .into_iter()
+                .collect(),
+            Self::HttpStatus => probe_values(&result.http, |it| it.code.map(|it| it.to_string())),
+            Self::HttpsStatus => probe_values(&result.https, |it| it.code.map(|it| it.to_string())),
+            Self::HttpTitle => probe_values(&result.http, |it| it.title.clone()),
+            Self::HttpsTitle => probe_values(&result.https, |it| it.title.clone()),
+            Self::HttpError => probe_values(&result.http, |it| it.error.clone()),
+            Self::HttpsError => probe_values(&result.https, |it| it.error.clone()),
+            Self::Url => probe
+                .map(|it| it.final_url.to_owned())
+                .into_iter()
+                .collect(),
+            Self::Server => probe
+                .and_then(|it| it.server.to_owned())
+                .into_iter()
+                .collect(),
+            Self::Technologies => probe.map(|it| it.technologies.clone()).unwrap_or_default(),
+            Self::Provider => result.provider.iter().cloned().collect(),
+            Self::Cdn => vec![result.cdn.to_string()],
+            Self::Asn => ip_infos()
+                .filter_map(|it| it.asn)
+                .map(|it| format!("AS{}", it))
+                .unique()
+                .collect(),
+            Self::Org => ip_infos()
+                .filter_map(|it| it.org.to_owned())
+                .unique()
+                .collect(),
+            Self::Country => ip_infos()
+                .filter_map(|it| it.country.to_owned())
+                .unique()
+                .collect(),
+            Self::Rcode => result.rcode.iter().cloned().collect(),
+            Self::Ttl => result
+                .records
+                .iter()
+                .map(|it| it.ttl)
+                .min()
+                .map(|it| it.to_string())
+                .into_iter()
+                .collect(),
+            Self::Nameserver => result.nameserver.iter().cloned().collect(),
+            Self::Latency => vec![result.latency_ms.to_string()],
+            Self::Source => result.source.iter().cloned().collect(),
+        }
+    }
+}
+
+/// 每个探测结果取一个值，没有值的位置为空
+fn probe_values(probes: &[HttpProbe], value: impl Fn(&HttpProbe) -> Option<String>) -> Vec<String> {
+    probes
+        .iter()
+        .map(|it| value(it).unwrap_or_default())
+        .collect()
+}
+
+/// 表头行
+pub fn header(columns: &[Column], delimiter: u8) -> String {
+    let fields = columns.iter().map(|it| it.name().to_owned()).collect_vec();
+    write_record(&fields, delimiter)
+}
+
+pub fn format_line(result: &ResolveResult, columns: &[Column], delimiter: u8) -> String {
+    let fields = columns.iter().map(|it| it.value(result)).collect_vec();
+    write_record(&fields, delimiter)
+}
+
+/// 按 RFC 4180 转义，包含分隔符、引号和换行的字段用引号包起来，换行使用 \r\n
+fn write_record(fields: &[String], delimiter: u8) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(vec![]);
+    // 写到内存里，不会失败
+    let _ = writer.write_record(fields);
+    let data = writer.into_inner().unwrap_or_default();
+    String::from_utf8(data).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::DnsRecord;
+
+    fn probe(code: Option<u16>, title: Option<&str>, error: Option<&str>) -> HttpProbe {
+        HttpProbe {
+            code,
+            title: title.map(|it| it.to_owned()),
+            error: error.map(|it| it.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn quotes_fields_with_delimiters_quotes_and_newlines() {
+        let fields = [
+            "plain".to_owned(),
+            "a, b".to_owned(),
+            r#"say "hi""#.to_owned(),
+            "line1\nline2".to_owned(),
+        ];
+        assert_eq!(
+            write_record(&fields, b','),
+            "plain,\"a, b\",\"say \"\"hi\"\"\",\"line1\nline2\"\r\n"
+        );
+        assert_eq!(
+            write_record(&["a, b".to_owned(), "c\td".to_owned()], b'\t'),
+            "a, b\t\"c\td\"\r\n"
+        );
+    }
+
+    #[test]
+    fn formats_line_in_column_order() {
+        let result = ResolveResult {
+            domain: "www.example.com".to_owned(),
+            ip: vec!["1.1.1.1".to_owned(), "2.2.2.2".to_owned()],
+            https: vec![probe(Some(200), Some("Hello, \"World\""), None)],
+            ..Default::default()
+        };
+        let columns = [Column::Domain, Column::Ips, Column::Status, Column::Title];
+        assert_eq!(header(&columns, b','), "domain,ips,status,title\r\n");
+        assert_eq!(
+            format_line(&result, &columns, b','),
+            "www.example.com,1.1.1.1;2.2.2.2,200,\"Hello, \"\"World\"\"\"\r\n"
+        );
+    }
+
+    #[test]
+    fn per_scheme_columns_stay_aligned() {
+        let result = ResolveResult {
+            http: vec![
+                probe(None, None, Some("timeout")),
+                probe(Some(200), Some("Home"), None),
+                probe(Some(404), None, None),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(Column::HttpStatus.values(&result), vec!["", "200", "404"]);
+        assert_eq!(Column::HttpTitle.values(&result), vec!["", "Home", ""]);
+        assert_eq!(Column::HttpError.values(&result), vec!["timeout", "", ""]);
+        assert!(Column::HttpsStatus.values(&result).is_empty());
+    }
+
+    #[test]
+    fn ttl_is_minimum_of_records() {
+        let record = |ttl| DnsRecord {
+            record_type: "A".to_owned(),
+            value: "1.1.1.1".to_owned(),
+            ttl,
+        };
+        let mut result = ResolveResult::default();
+        assert!(Column::Ttl.values(&result).is_empty());
+        result.records = vec![record(600), record(60), record(300)];
+        assert_eq!(Column::Ttl.values(&result), vec!["60"]);
+    }
+
+    #[test]
+    fn column_names_round_trip() {
+        for name in Column::NAMES {
+            assert_eq!(Column::from_name(name).map(|it| it.name()), Some(name));
+        }
+        assert!(Column::from_name("unknown").is_none());
+    }
+}
@@ -0,0 +1,40 @@
+use super::primary_probe;
+use crate::context::ResolveResult;
+
+/// 常见的可以被接管的第三方服务，CNAME 指向这些服务但页面不存在时可能被接管
+const SERVICES: [&str; 18] = [
+    "github.io",
+    "herokuapp.com",
+    "herokudns.com",
+    "s3.amazonaws.com",
+    "s3-website",
+    "cloudfront.net",
+    "azurewebsites.net",
+    "cloudapp.net",
+    "trafficmanager.net",
+    "blob.core.windows.net",
+    "pantheonsite.io",
+    "ghost.io",
+    "myshopify.com",
+    "surge.sh",
+    "bitbucket.io",
+    "readthedocs.io",
+    "netlify.app",
+    "wordpress.com",
+];
+
+/// 判断域名是否可能被子域名接管，返回原因
+/// CNAME 解析不到 IP（悬空 CNAME），或者 CNAME 指向第三方服务但页面返回 404
+pub fn takeover_reason(result: &ResolveResult) -> Option<String> {
+    let cname = result.cname.last()?;
+    if result.ip.is_empty() {
+        return Some(format!("dangling CNAME {}", cname));
+    }
+
+    let target = cname.trim_end_matches('.').to_lowercase();
+    let service = SERVICES.iter().find(|it| target.contains(*it))?;
+    match primary_probe(result).and_then(|it| it.code) {
+        Some(404) => Some(format!("CNAME to {} returns 404", service)),
+        _ => None,
+    }
+}
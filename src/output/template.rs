@@ -0,0 +1,298 @@
+use std::{iter::Peekable, str::Chars};
+
+use super::Column;
+use crate::context::ResolveResult;
+
+/// 多个值的字段没有使用 join 时的分隔符
+const DEFAULT_SEPARATOR: &str = ",";
+
+/// 自定义输出模板，例如 {domain}\t{ips|join(",")}\t{status|default("-")}
+/// 字段名和 --columns 的列名相同，{{ 和 }} 输出花括号，\t、\n 和 \\ 会被转义
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Field(Column, Vec<Filter>),
+}
+
+/// 字段的过滤器，按顺序执行
+#[derive(Debug, Clone)]
+enum Filter {
+    // 用指定的分隔符拼接多个值
+    Join(String),
+    // 没有值或者值为空时使用默认值
+    Default(String),
+    // 最多保留指定数量的字符
+    Truncate(usize),
+}
+
+/// 过滤器执行过程中的值，join 之前是列表，之后是字符串
+enum Value {
+    List(Vec<String>),
+    Text(String),
+}
+
+impl Value {
+    fn into_text(self) -> String {
+        match self {
+            Self::List(values) => values.join(DEFAULT_SEPARATOR),
+            Self::Text(text) => text,
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    let field = read_field(&mut chars)?;
+                    parts.push(parse_field(&field)?);
+                }
+                '}' => return Err("模板有误，多余的 }，输出 } 需要写成 }}".to_owned()),
+                '\\' => push_escaped(chars.next(), &mut literal),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// 渲染一条结果，末尾带换行
+    pub fn render(&self, result: &ResolveResult) -> String {
+        let mut line = String::new();
+        for part in self.parts.iter() {
+            match part {
+                Part::Literal(literal) => line.push_str(literal),
+                Part::Field(column, filters) => {
+                    let mut value = Value::List(column.values(result));
+                    for filter in filters {
+                        value = filter.apply(value);
+                    }
+                    line.push_str(&value.into_text());
+                }
+            }
+        }
+        line.push('\n');
+        line
+    }
+}
+
+impl Filter {
+    fn apply(&self, value: Value) -> Value {
+        match self {
+            Self::Join(separator) => match value {
+                Value::List(values) => Value::Text(values.join(separator)),
+                text => text,
+            },
+            Self::Default(default) => {
+                let empty = match &value {
+                    Value::List(values) => values.iter().all(|it| it.is_empty()),
+                    Value::Text(text) => text.is_empty(),
+                };
+                if empty {
+                    Value::Text(default.to_owned())
+                } else {
+                    value
+                }
+            }
+            Self::Truncate(length) => {
+                Value::Text(value.into_text().chars().take(*length).collect())
+            }
+        }
+    }
+}
+
+/// 读取 { 之后到匹配的 } 之间的内容，引号中的 } 不算
+fn read_field(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut field = String::new();
+    let mut quote = None;
+    for c in chars.by_ref() {
+        match (quote, c) {
+            (None, '}') => return Ok(field),
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => {}
+        }
+        field.push(c);
+    }
+    Err(format!("模板有误，{{{} 缺少 }}", field))
+}
+
+/// 解析 name|filter|filter(arg) 形式的字段
+fn parse_field(field: &str) -> Result<Part, String> {
+    let mut items = split_filters(field).into_iter();
+    let name = items.next().unwrap_or_default();
+    let column = Column::from_name(name.trim()).ok_or_else(|| {
+        format!(
+            "模板有误，未知的字段: {}，可选: {}",
+            name.trim(),
+            Column::NAMES.join(",")
+        )
+    })?;
+    let filters = items
+        .map(|it| parse_filter(it.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Part::Field(column, filters))
+}
+
+/// 按 | 切分，引号中的 | 不算
+fn split_filters(field: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut quote = None;
+    let mut start = 0;
+    for (idx, c) in field.char_indices() {
+        match (quote, c) {
+            (None, '|') => {
+                items.push(&field[start..idx]);
+                start = idx + 1;
+            }
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => {}
+        }
+    }
+    items.push(&field[start..]);
+    items
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    let (name, arg) = match filter.split_once('(') {
+        Some((name, rest)) => match rest.trim_end().strip_suffix(')') {
+            Some(arg) => (name.trim(), Some(arg.trim())),
+            None => return Err(format!("模板有误，过滤器缺少 ): {}", filter)),
+        },
+        None => (filter, None),
+    };
+    match (name, arg) {
+        ("join", None) => Ok(Filter::Join(DEFAULT_SEPARATOR.to_owned())),
+        ("join", Some(arg)) => Ok(Filter::Join(unquote(arg)?)),
+        ("default", Some(arg)) => Ok(Filter::Default(unquote(arg)?)),
+        ("truncate", Some(arg)) => arg
+            .parse::<usize>()
+            .map(Filter::Truncate)
+            .map_err(|_| format!("模板有误，truncate 的参数必须是数字: {}", filter)),
+        _ => Err(format!(
+            "模板有误，未知的过滤器: {}，可选: join(\",\")、default(\"-\")、truncate(30)",
+            filter
+        )),
+    }
+}
+
+/// 去掉参数两边的引号，并转义 \t、\n 和 \\
+fn unquote(arg: &str) -> Result<String, String> {
+    let inner = match arg.chars().next() {
+        Some(q @ ('"' | '\'')) if arg.len() >= 2 && arg.ends_with(q) => &arg[1..arg.len() - 1],
+        _ => return Err(format!("模板有误，过滤器的参数需要用引号括起来: {}", arg)),
+    };
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => push_escaped(chars.next(), &mut value),
+            _ => value.push(c),
+        }
+    }
+    Ok(value)
+}
+
+/// 处理 \ 后面的字符，只转义 \t、\n 和 \\，其他的原样保留
+fn push_escaped(next: Option<char>, output: &mut String) {
+    match next {
+        Some('t') => output.push('\t'),
+        Some('n') => output.push('\n'),
+        Some('\\') => output.push('\\'),
+        Some(other) => {
+            output.push('\\');
+            output.push(other);
+        }
+        None => output.push('\\'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::HttpProbe;
+
+    fn result() -> ResolveResult {
+        ResolveResult {
+            domain: "www.example.com".to_owned(),
+            ip: vec!["1.1.1.1".to_owned(), "2.2.2.2".to_owned()],
+            https: vec![HttpProbe {
+                code: Some(200),
+                title: Some("Example Domain".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn render(template: &str) -> String {
+        Template::parse(template).unwrap().render(&result())
+    }
+
+    #[test]
+    fn renders_fields_and_escapes() {
+        assert_eq!(
+            render(r"{domain}\t{ips}\t{status}\\n"),
+            "www.example.com\t1.1.1.1,2.2.2.2\t200\\n\n"
+        );
+        assert_eq!(render("{{{domain}}}"), "{www.example.com}\n");
+        assert_eq!(render(r"a\qb"), "a\\qb\n");
+    }
+
+    #[test]
+    fn quoted_arguments_may_contain_braces_and_pipes() {
+        assert_eq!(render(r#"{ips|join(" | ")}"#), "1.1.1.1 | 2.2.2.2\n");
+        assert_eq!(render("{ips|join('}')}"), "1.1.1.1}2.2.2.2\n");
+        assert_eq!(render(r#"{ips|join("\t")}"#), "1.1.1.1\t2.2.2.2\n");
+        assert_eq!(render("{ips|join}"), "1.1.1.1,2.2.2.2\n");
+    }
+
+    #[test]
+    fn default_and_truncate_filters() {
+        assert_eq!(render(r#"{cnames|default("-")}"#), "-\n");
+        assert_eq!(render(r#"{status|default("-")}"#), "200\n");
+        assert_eq!(render("{title|truncate(7)}"), "Example\n");
+        assert_eq!(
+            render(r#"{ips|join(";")|truncate(9)|default("-")}"#),
+            "1.1.1.1;2\n"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(Template::parse("{domain").is_err());
+        assert!(Template::parse("domain}").is_err());
+        assert!(Template::parse("{unknown}").is_err());
+        assert!(Template::parse("{ips|upper}").is_err());
+        assert!(Template::parse("{ips|join(\",\"}").is_err());
+        assert!(Template::parse("{ips|join(,)}").is_err());
+        assert!(Template::parse("{ips|default}").is_err());
+        assert!(Template::parse("{ips|default(-)}").is_err());
+        assert!(Template::parse("{title|truncate}").is_err());
+        assert!(Template::parse("{title|truncate(abc)}").is_err());
+        assert!(Template::parse("{title|truncate(-1)}").is_err());
+    }
+}
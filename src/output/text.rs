@@ -0,0 +1,36 @@
+use crate::context::ResolveResult;
+
+pub fn format_line(result: &ResolveResult) -> String {
+    format!(
+        "{} - {:?} - {:?} - {:?} - {:?} - {} - {} - {}ms - {:?} - {:?} - {} - {:?} - {}\n",
+        result.domain,
+        result.ip,
+        result.cname,
+        result
+            .http
+            .iter()
+            .map(|it| it.to_string())
+            .collect::<Vec<_>>(),
+        result
+            .https
+            .iter()
+            .map(|it| it.to_string())
+            .collect::<Vec<_>>(),
+        result.nameserver.as_deref().unwrap_or_default(),
+        result.rcode.as_deref().unwrap_or_default(),
+        result.latency_ms,
+        result
+            .records
+            .iter()
+            .map(|it| it.to_string())
+            .collect::<Vec<_>>(),
+        result.ecs,
+        result.provider.as_deref().unwrap_or_default(),
+        result
+            .ip_info
+            .iter()
+            .map(|(ip, info)| format!("{} {}", ip, info))
+            .collect::<Vec<_>>(),
+        result.source.as_deref().unwrap_or_default(),
+    )
+}
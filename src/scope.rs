@@ -0,0 +1,172 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use regex::Regex;
+use tokio::fs::read_to_string;
+
+/// 扫描范围规则，每行一条，格式:
+///   include <pattern>   只扫描匹配的域名，没有 include 规则时全部域名都在范围内
+///   exclude <pattern>   不扫描匹配的域名，优先级高于 include
+///   block <ip|cidr>     解析到这些 IP 的域名不发起 HTTP 请求
+/// pattern 默认为 glob（支持 * 和 ?），以 re: 开头时为正则，均不区分大小写
+#[derive(Default)]
+pub struct Scope {
+    includes: Vec<Regex>,
+    excludes: Vec<Regex>,
+    blocklist: Vec<IpNet>,
+}
+
+impl Scope {
+    /// 未指定范围文件时不做任何限制
+    pub async fn load(path: Option<&String>) -> Result<Self, String> {
+        match path {
+            Some(path) => {
+                let content = read_to_string(path).await.map_err(|e| {
+                    format!("Read scope file failed, path: {}, error: {:?}", path, e)
+                })?;
+                Self::parse(&content)
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut scope = Self::default();
+
+        for (lineno, line) in content.lines().enumerate() {
+            // skip empty line and comment line
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (directive, value) = match line.split_once(char::is_whitespace) {
+                Some((directive, value)) => (directive, value.trim()),
+                None => {
+                    return Err(format!(
+                        "Invalid scope rule at line {}: {}",
+                        lineno + 1,
+                        line
+                    ))
+                }
+            };
+            match directive {
+                "include" => scope.includes.push(Self::build_pattern(value)?),
+                "exclude" => scope.excludes.push(Self::build_pattern(value)?),
+                "block" => {
+                    let cidr = match value.parse::<IpNet>() {
+                        Ok(cidr) => cidr,
+                        Err(_) => match value.parse::<IpAddr>() {
+                            Ok(ip) => IpNet::from(ip),
+                            Err(_) => {
+                                return Err(format!(
+                                    "Invalid IP/CIDR at line {}: {}",
+                                    lineno + 1,
+                                    line
+                                ))
+                            }
+                        },
+                    };
+                    scope.blocklist.push(cidr);
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid scope directive at line {}: {}",
+                        lineno + 1,
+                        line
+                    ))
+                }
+            }
+        }
+
+        Ok(scope)
+    }
+
+    /// 把 glob 或者 re: 开头的正则转换成不区分大小写的正则
+    fn build_pattern(pattern: &str) -> Result<Regex, String> {
+        let regex = match pattern.strip_prefix("re:") {
+            Some(regex) => format!("(?i){}", regex),
+            None => {
+                let mut regex = String::from("(?i)^");
+                for c in pattern.chars() {
+                    match c {
+                        '*' => regex.push_str(".*"),
+                        '?' => regex.push('.'),
+                        _ => regex.push_str(&regex::escape(&c.to_string())),
+                    }
+                }
+                regex.push('$');
+                regex
+            }
+        };
+        Regex::new(&regex)
+            .map_err(|e| format!("Invalid scope pattern: {}, error: {:?}", pattern, e))
+    }
+
+    /// 域名是否在扫描范围内
+    pub fn contains_domain(&self, domain: &str) -> bool {
+        if self.excludes.iter().any(|it| it.is_match(domain)) {
+            return false;
+        }
+        self.includes.is_empty() || self.includes.iter().any(|it| it.is_match(domain))
+    }
+
+    /// 是否配置了 IP 黑名单
+    pub fn has_blocklist(&self) -> bool {
+        !self.blocklist.is_empty()
+    }
+
+    /// IP 是否在黑名单中
+    pub fn is_blocked(&self, ip: &str) -> bool {
+        match ip.parse::<IpAddr>() {
+            Ok(ip) => self.blocklist.iter().any(|it| it.contains(&ip)),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_pattern_matches_whole_domain() {
+        let pattern = Scope::build_pattern("*.example.com").unwrap();
+        assert!(pattern.is_match("www.example.com"));
+        assert!(pattern.is_match("a.b.EXAMPLE.com"));
+        assert!(!pattern.is_match("example.com"));
+        assert!(!pattern.is_match("www.example.com.evil.net"));
+        assert!(!pattern.is_match("www-example.com"));
+    }
+
+    #[test]
+    fn glob_question_mark_matches_one_char() {
+        let pattern = Scope::build_pattern("api?.example.com").unwrap();
+        assert!(pattern.is_match("api1.example.com"));
+        assert!(!pattern.is_match("api.example.com"));
+        assert!(!pattern.is_match("api12.example.com"));
+    }
+
+    #[test]
+    fn regex_pattern_is_not_anchored() {
+        let pattern = Scope::build_pattern(r"re:^(dev|test)\d*\.").unwrap();
+        assert!(pattern.is_match("DEV01.example.com"));
+        assert!(!pattern.is_match("www.dev.example.com"));
+        assert!(Scope::build_pattern("re:(").is_err());
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let scope = Scope::parse(
+            "# comment\ninclude *.example.com\nexclude admin.*\nblock 10.0.0.0/8\nblock 192.168.1.1\n",
+        )
+        .unwrap();
+        assert!(scope.contains_domain("www.example.com"));
+        assert!(!scope.contains_domain("admin.example.com"));
+        assert!(!scope.contains_domain("www.example.org"));
+        assert!(scope.is_blocked("10.1.2.3"));
+        assert!(scope.is_blocked("192.168.1.1"));
+        assert!(!scope.is_blocked("192.168.1.2"));
+        assert!(Scope::parse("allow *.example.com").is_err());
+    }
+}